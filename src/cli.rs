@@ -0,0 +1,274 @@
+use crate::rz::*;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+usage: rz [SCENE] [options]
+
+Renders SCENE, a YAML scene file, or the built-in demo scene without one.
+
+options:
+  -o, --output PATH     where to write the image (default: output/world.png)
+      --width N         image width in pixels
+      --height N        image height in pixels
+      --samples N       rays per pixel, rounded up to a square (default: 1)
+      --threads N       render threads (default: all cores)
+      --max-depth N     reflection and refraction bounces (default: 5)
+      --integrator I    whitted or path (default: whitted)
+      --tonemap OP      clamp, reinhard or aces (default: clamp)
+      --exposure STOPS  brighten or darken before tone mapping (default: 0)
+      --srgb            encode with the sRGB curve
+  -q, --quiet           don't show progress
+  -h, --help            show this message
+
+--max-depth only limits the whitted integrator; path tracing ends paths at
+random once they grow dim, up to a fixed limit of 64 bounces.
+
+Images saved as .hdr or .exr keep the linear colors and skip tone mapping.";
+
+/// What the user asked `rz` to do.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub scene: Option<String>,
+    pub output: String,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub samples: usize,
+    pub threads: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub integrator: Integrator,
+    pub tone_mapping: ToneMapping,
+    pub quiet: bool,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            scene: None,
+            output: "output/world.png".to_string(),
+            width: None,
+            height: None,
+            samples: 1,
+            threads: None,
+            max_depth: None,
+            integrator: Integrator::Whitted,
+            tone_mapping: ToneMapping::default(),
+            quiet: false,
+            help: false,
+        }
+    }
+}
+
+impl Options {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value =
+                |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+
+            match arg.as_str() {
+                "-o" | "--output" => options.output = value(&arg)?,
+                "--width" => options.width = Some(positive(&arg, &value(&arg)?)?),
+                "--height" => options.height = Some(positive(&arg, &value(&arg)?)?),
+                "--samples" => options.samples = positive(&arg, &value(&arg)?)?,
+                "--threads" => options.threads = Some(positive(&arg, &value(&arg)?)?),
+                "--max-depth" => options.max_depth = Some(number(&arg, &value(&arg)?)?),
+                "--integrator" => {
+                    options.integrator = match value(&arg)?.as_str() {
+                        "whitted" => Integrator::Whitted,
+                        "path" => Integrator::PathTraced,
+                        other => return Err(format!("unknown integrator '{}'", other)),
+                    }
+                }
+                "--tonemap" => {
+                    options.tone_mapping.operator = match value(&arg)?.as_str() {
+                        "clamp" => ToneMap::Clamp,
+                        "reinhard" => ToneMap::Reinhard,
+                        "aces" => ToneMap::Aces,
+                        other => return Err(format!("unknown tone mapping '{}'", other)),
+                    }
+                }
+                "--exposure" => {
+                    let stops = value(&arg)?;
+                    options.tone_mapping.exposure = stops
+                        .parse()
+                        .map_err(|_| format!("{} expects a number, got '{}'", arg, stops))?;
+                }
+                "--srgb" => options.tone_mapping.srgb = true,
+                "-q" | "--quiet" => options.quiet = true,
+                "-h" | "--help" => options.help = true,
+                _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+                _ if options.scene.is_none() => options.scene = Some(arg),
+                _ => return Err(format!("unexpected argument '{}'", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// The side of the square grid of rays shot through each pixel.
+    pub fn samples_per_side(&self) -> usize {
+        let mut n = 1;
+        while n * n < self.samples {
+            n += 1;
+        }
+        n
+    }
+}
+
+fn number(name: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a whole number, got '{}'", name, value))
+}
+
+fn positive(name: &str, value: &str) -> Result<usize, String> {
+    match number(name, value)? {
+        0 => Err(format!("{} must be at least 1", name)),
+        n => Ok(n),
+    }
+}
+
+/// Formats render progress as e.g. `42% (eta 1m05s)`.
+pub fn progress_line(done: usize, total: usize, elapsed: Duration) -> String {
+    let fraction = done as f64 / total.max(1) as f64;
+    let eta = if done == 0 {
+        "eta --".to_string()
+    } else {
+        let remaining = elapsed.as_secs_f64() * (1.0 - fraction) / fraction;
+        format!(
+            "eta {}",
+            format_duration(Duration::from_secs_f64(remaining))
+        )
+    };
+
+    format!("{:3.0}% ({})", fraction * 100.0, eta)
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn parse_defaults() {
+    assert_eq!(Options::parse(args("")), Ok(Options::default()));
+}
+
+#[test]
+fn parse_all_options() {
+    let options = Options::parse(args(
+        "scene.yml -o out.png --width 320 --height 240 --samples 16 --threads 4 --max-depth 2 -q",
+    ))
+    .unwrap();
+
+    assert_eq!(
+        options,
+        Options {
+            scene: Some("scene.yml".to_string()),
+            output: "out.png".to_string(),
+            width: Some(320),
+            height: Some(240),
+            samples: 16,
+            threads: Some(4),
+            max_depth: Some(2),
+            integrator: Integrator::Whitted,
+            tone_mapping: ToneMapping::default(),
+            quiet: true,
+            help: false,
+        }
+    );
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        Options::parse(args("--width")),
+        Err("--width needs a value".to_string())
+    );
+    assert_eq!(
+        Options::parse(args("--width wide")),
+        Err("--width expects a whole number, got 'wide'".to_string())
+    );
+    assert_eq!(
+        Options::parse(args("--threads 0")),
+        Err("--threads must be at least 1".to_string())
+    );
+    assert_eq!(
+        Options::parse(args("--fast")),
+        Err("unknown option '--fast'".to_string())
+    );
+    assert_eq!(
+        Options::parse(args("a.yml b.yml")),
+        Err("unexpected argument 'b.yml'".to_string())
+    );
+}
+
+#[test]
+fn parse_tone_mapping() {
+    let options = Options::parse(args("--tonemap aces --exposure -1.5 --srgb")).unwrap();
+
+    assert_eq!(
+        options.tone_mapping,
+        ToneMapping::new(ToneMap::Aces)
+            .with_exposure(-1.5)
+            .with_srgb(true)
+    );
+    assert_eq!(
+        Options::parse(args("--tonemap filmic")),
+        Err("unknown tone mapping 'filmic'".to_string())
+    );
+}
+
+#[test]
+fn parse_integrator() {
+    assert_eq!(
+        Options::parse(args("--integrator path"))
+            .unwrap()
+            .integrator,
+        Integrator::PathTraced
+    );
+    assert_eq!(
+        Options::parse(args("--integrator photons")),
+        Err("unknown integrator 'photons'".to_string())
+    );
+}
+
+#[test]
+fn samples_round_up_to_a_square() {
+    let samples = |n| Options {
+        samples: n,
+        ..Options::default()
+    };
+
+    assert_eq!(samples(1).samples_per_side(), 1);
+    assert_eq!(samples(4).samples_per_side(), 2);
+    assert_eq!(samples(5).samples_per_side(), 3);
+}
+
+#[test]
+fn progress_estimates_remaining_time() {
+    assert_eq!(
+        progress_line(0, 100, Duration::from_secs(0)),
+        "  0% (eta --)"
+    );
+    assert_eq!(
+        progress_line(25, 100, Duration::from_secs(30)),
+        " 25% (eta 1m30s)"
+    );
+    assert_eq!(
+        progress_line(100, 100, Duration::from_secs(8)),
+        "100% (eta 0s)"
+    );
+}
@@ -1,18 +1,136 @@
 #![allow(dead_code)]
+// Lints the original code predates
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::bool_comparison,
+    clippy::legacy_numeric_constants,
+    clippy::manual_is_multiple_of,
+    clippy::unnecessary_cast
+)]
 
+mod cli;
 mod rz;
+use cli::*;
 use rz::*;
+use std::env;
 use std::f64::consts::PI;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 
-fn main() {
+fn main() -> ExitCode {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("rz: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    if options.help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("rz: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let Scene {
+        mut world,
+        mut camera,
+        warnings,
+    } = match &options.scene {
+        Some(path) => Scene::load(path).map_err(|err| match err {
+            SceneError::Io(..) => err.to_string(),
+            _ => format!("{}: {}", path, err),
+        })?,
+        None => demo_scene(),
+    };
+    for warning in warnings {
+        eprintln!(
+            "rz: {}: warning: {}",
+            options.scene.as_deref().unwrap_or_default(),
+            warning
+        );
+    }
+
+    if let Some(max_depth) = options.max_depth {
+        world.max_depth = max_depth;
+    }
+
+    // With only one side given, keep the scene's aspect ratio.
+    let (width, height) = match (options.width, options.height) {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, (width * camera.vsize() / camera.hsize()).max(1)),
+        (None, Some(height)) => ((height * camera.hsize() / camera.vsize()).max(1), height),
+        (None, None) => (camera.hsize(), camera.vsize()),
+    };
+    camera = camera.with_size(width, height);
+    if let Some(threads) = options.threads {
+        camera = camera.with_threads(threads);
+    }
+    if options.samples > 1 {
+        camera = camera.with_sampling(Sampling::Jittered(options.samples_per_side()));
+    }
+    camera = camera.with_integrator(options.integrator);
+
+    let start = Instant::now();
+    let shown = AtomicUsize::new(0);
+    let image = camera.render_with_progress(&world, |done, total| {
+        // Only redraw when the whole percentage goes up.
+        let percent = done * 100 / total.max(1);
+        if !options.quiet && shown.fetch_max(percent + 1, Ordering::Relaxed) <= percent {
+            eprint!(
+                "\rrendering {}",
+                progress_line(done, total, start.elapsed())
+            );
+        }
+    });
+    if !options.quiet {
+        eprintln!(
+            "\rrendered {}x{} in {}        ",
+            width,
+            height,
+            format_duration(start.elapsed())
+        );
+    }
+
+    if let Some(dir) = Path::new(&options.output).parent() {
+        fs::create_dir_all(dir)
+            .map_err(|err| format!("couldn't create {}: {}", dir.display(), err))?;
+    }
+    image
+        .save_with(&options.output, &options.tone_mapping)
+        .map_err(|err| format!("couldn't write {}: {}", options.output, err))
+}
+
+/// The scene rendered when no scene file is given.
+fn demo_scene() -> Scene {
     let floor = Plane {
-        transform: Matrix::identity(),
+        transform: Transform::identity(),
         material: Material {
             color: color(0.0, 0.0, 1.0),
             ambient: 0.1,
             diffuse: 0.7,
             specular: 0.2,
             shininess: 20.0,
+            reflective: 0.3,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            pattern: Some(Pattern::checkers(
+                color(0.0, 0.0, 1.0),
+                color(0.9, 0.9, 0.9),
+            )),
+            emissive: Color::black(),
         },
     }
     .into();
@@ -20,25 +138,36 @@ fn main() {
     let left = Sphere {
         transform: Matrix::identity()
             .scale(0.33, 0.33, 0.33)
-            .translate(-1.5, 0.33, -0.75),
+            .translate(-1.5, 0.33, -0.75)
+            .into(),
         material: Material {
             color: color(1.0, 0.8, 0.1),
             ambient: 0.1,
             diffuse: 0.7,
             specular: 0.2,
             shininess: 20.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            pattern: None,
+            emissive: Color::black(),
         },
     }
     .into();
 
     let middle = Sphere {
-        transform: Matrix::identity().translate(-0.5, 1.0, 0.5),
+        transform: Matrix::identity().translate(-0.5, 1.0, 0.5).into(),
         material: Material {
             color: color(0.1, 1.0, 0.5),
             ambient: 0.1,
             diffuse: 0.7,
             specular: 0.2,
             shininess: 20.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            pattern: None,
+            emissive: Color::black(),
         },
     }
     .into();
@@ -46,31 +175,122 @@ fn main() {
     let right = Sphere {
         transform: Matrix::identity()
             .scale(0.5, 0.5, 0.5)
-            .translate(1.5, 0.5, -0.5),
+            .translate(1.5, 0.5, -0.5)
+            .into(),
         material: Material {
             color: color(1.0, 0.3, 0.1),
             ambient: 0.1,
             diffuse: 0.7,
             specular: 0.2,
             shininess: 5.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            pattern: None,
+            emissive: Color::black(),
         },
     }
     .into();
 
-    let world = World {
-        light: PointLight {
+    let mut world = World::new();
+    world.lights = vec![
+        PointLight {
             position: point(-10.0, 10.0, -10.0),
-            intensity: Color::white(),
-        },
-        objects: vec![floor, left, middle, right],
-    };
+            intensity: color(0.8, 0.8, 0.8),
+        }
+        .into(),
+        PointLight {
+            position: point(10.0, 5.0, -10.0),
+            intensity: color(0.3, 0.3, 0.3),
+        }
+        .into(),
+    ];
+    *world.objects_mut() = vec![floor, left, middle, right];
+    world.build_bvh();
 
     let mut camera = Camera::new(800, 400, PI / 3.0);
-    camera.transform = Matrix::view(
+    camera.set_transform(Matrix::view(
         point(0.0, 1.5, -5.0),
         point(0.0, 1.0, 0.0),
         vector(0.0, 1.0, 0.0),
+    ));
+
+    Scene {
+        world,
+        camera,
+        warnings: vec![],
+    }
+}
+
+#[cfg(test)]
+fn run_scene(name: &str, source: &str, options: Options) -> Result<(), String> {
+    let dir = env::temp_dir();
+    let scene = dir.join(format!("rz-cli-{}.yml", name));
+    fs::write(&scene, source).unwrap();
+
+    run(&Options {
+        scene: Some(scene.to_string_lossy().into_owned()),
+        output: dir
+            .join(format!("rz-cli-{}.png", name))
+            .to_string_lossy()
+            .into_owned(),
+        quiet: true,
+        ..options
+    })
+}
+
+#[cfg(test)]
+const TINY_CAMERA: &str = "
+- add: camera
+  width: 4
+  height: 4
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+";
+
+#[test]
+fn degenerate_transform_is_a_scene_error() {
+    let source = format!(
+        "{}- add: sphere\n  transform: [[scale, 0, 1, 1]]\n",
+        TINY_CAMERA
     );
+    let err = run_scene("degenerate", &source, Options::default()).unwrap_err();
 
-    camera.render(&world).save("output/world.png")
+    assert!(
+        err.ends_with("line 10: transform is not invertible"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn corrupt_texture_is_a_scene_error() {
+    let texture = env::temp_dir().join("rz-cli-corrupt.ppm");
+    fs::write(&texture, b"P6 100000000000 100000000000 255\n\0\0\0").unwrap();
+    let source = format!(
+        "{}- add: sphere\n  material:\n    pattern:\n      type: texture\n      mapping: spherical\n      file: {}\n",
+        TINY_CAMERA,
+        texture.display()
+    );
+    let err = run_scene("texture", &source, Options::default()).unwrap_err();
+
+    assert!(err.contains("more than the file holds"), "{}", err);
+}
+
+#[test]
+fn zero_sized_camera_is_a_scene_error() {
+    let source = TINY_CAMERA.replace("width: 4", "width: 0");
+    let options = Options {
+        width: Some(8),
+        ..Options::default()
+    };
+    let err = run_scene("zero-size", &source, options).unwrap_err();
+
+    assert!(
+        err.ends_with("line 3: camera 'width' must be at least 1"),
+        "{}",
+        err
+    );
 }
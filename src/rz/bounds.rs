@@ -0,0 +1,169 @@
+#![allow(unused_imports)]
+use super::matrix::*;
+use super::ray::*;
+use super::tuple::*;
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounds {
+    pub min: Tuple,
+    pub max: Tuple,
+}
+
+impl Bounds {
+    pub fn new(min: Tuple, max: Tuple) -> Self {
+        Bounds { min, max }
+    }
+
+    /// A box that contains nothing, and grows to fit whatever is added to it.
+    pub fn empty() -> Self {
+        Bounds {
+            min: point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn infinite() -> Self {
+        Bounds {
+            min: point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            max: point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        }
+    }
+
+    pub fn is_finite(&self) -> bool {
+        (0..3).all(|i| self.min[i].is_finite() && self.max[i].is_finite())
+    }
+
+    pub fn add_point(&mut self, p: Tuple) {
+        for i in 0..3 {
+            self.min[i] = self.min[i].min(p[i]);
+            self.max[i] = self.max[i].max(p[i]);
+        }
+    }
+
+    pub fn merged(&self, other: &Bounds) -> Self {
+        let mut bounds = *self;
+        bounds.add_point(other.min);
+        bounds.add_point(other.max);
+        bounds
+    }
+
+    pub fn centroid(&self) -> Tuple {
+        point(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+        )
+    }
+
+    /// The box that contains this one after it has been transformed by `m`.
+    pub fn transformed(&self, m: Matrix<4>) -> Self {
+        if !self.is_finite() {
+            // Infinity times the zeros in a transform gives NaN, so play it safe
+            return Bounds::infinite();
+        }
+
+        let mut bounds = Bounds::empty();
+        for x in [self.min.x, self.max.x] {
+            for y in [self.min.y, self.max.y] {
+                for z in [self.min.z, self.max.z] {
+                    bounds.add_point(m * point(x, y, z));
+                }
+            }
+        }
+
+        bounds
+    }
+
+    /// Whether the ray passes through the box, using the same slab test as `Cube`.
+    pub fn intersects(&self, ray: Ray) -> bool {
+        let mut tmin = f64::NEG_INFINITY;
+        let mut tmax = f64::INFINITY;
+
+        for i in 0..3 {
+            let inverse = 1.0 / ray.direction[i];
+            let mut t0 = (self.min[i] - ray.origin[i]) * inverse;
+            let mut t1 = (self.max[i] - ray.origin[i]) * inverse;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            // NaN shows up for rays lying in a slab's boundary; max/min skip it
+            tmin = tmin.max(t0);
+            tmax = tmax.min(t1);
+        }
+
+        tmin <= tmax && tmax >= 0.0
+    }
+}
+
+#[test]
+fn adding_points_to_empty_bounds() {
+    let mut b = Bounds::empty();
+    b.add_point(point(-5.0, 2.0, 0.0));
+    b.add_point(point(7.0, 0.0, -3.0));
+
+    assert_eq!(b.min, point(-5.0, 0.0, -3.0));
+    assert_eq!(b.max, point(7.0, 2.0, 0.0));
+}
+
+#[test]
+fn merging_bounds() {
+    let a = Bounds::new(point(-5.0, -2.0, 0.0), point(7.0, 4.0, 4.0));
+    let b = Bounds::new(point(8.0, -7.0, -2.0), point(14.0, 2.0, 8.0));
+    let merged = a.merged(&b);
+
+    assert_eq!(merged.min, point(-5.0, -7.0, -2.0));
+    assert_eq!(merged.max, point(14.0, 4.0, 8.0));
+}
+
+#[test]
+fn transforming_bounds() {
+    let b = Bounds::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0));
+    let m = Matrix::rotation_x(std::f64::consts::PI / 4.0)
+        * Matrix::rotation_y(std::f64::consts::PI / 4.0);
+    let t = b.transformed(m);
+
+    let sq2 = 2_f64.sqrt();
+    let edge = 1.0 + sq2 / 2.0;
+    assert_eq!(t.min, point(-sq2, -edge, -edge));
+    assert_eq!(t.max, point(sq2, edge, edge));
+}
+
+#[test]
+fn transforming_infinite_bounds() {
+    let b = Bounds::new(
+        point(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+        point(f64::INFINITY, 0.0, f64::INFINITY),
+    );
+
+    let t = b.transformed(Matrix::rotation_x(1.0));
+
+    assert!(!t.is_finite());
+    assert!(t.intersects(Ray::new(point(5.0, 5.0, 5.0), vector(0.0, 1.0, 0.0))));
+}
+
+#[test]
+fn intersecting_rays_with_bounds() {
+    let b = Bounds::new(point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0));
+    let cases = [
+        (point(15.0, 1.0, 2.0), vector(-1.0, 0.0, 0.0), true),
+        (point(-5.0, -1.0, 4.0), vector(1.0, 0.0, 0.0), true),
+        (point(7.0, 6.0, 5.0), vector(0.0, -1.0, 0.0), true),
+        (point(9.0, -5.0, 6.0), vector(0.0, 1.0, 0.0), true),
+        (point(8.0, 2.0, 12.0), vector(0.0, 0.0, -1.0), true),
+        (point(6.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), true),
+        (point(8.0, 1.0, 3.5), vector(0.0, 0.0, 1.0), true),
+        (point(9.0, -1.0, -8.0), vector(2.0, 4.0, 6.0), false),
+        (point(8.0, 3.0, -4.0), vector(6.0, 2.0, 4.0), false),
+        (point(9.0, -1.0, -2.0), vector(4.0, 6.0, 2.0), false),
+        (point(4.0, 0.0, 9.0), vector(0.0, 0.0, -1.0), false),
+        (point(8.0, 6.0, -1.0), vector(0.0, -1.0, 0.0), false),
+        (point(12.0, 5.0, 4.0), vector(-1.0, 0.0, 0.0), false),
+        (point(8.0, 1.0, 10.0), vector(0.0, 0.0, 1.0), false),
+    ];
+
+    for (origin, direction, expected) in cases {
+        assert_eq!(b.intersects(Ray::new(origin, direction)), expected);
+    }
+}
@@ -0,0 +1,235 @@
+#![allow(unused_imports)]
+use super::bounds::*;
+use super::camera::*;
+use super::intersection::*;
+use super::matrix::*;
+use super::ray::*;
+use super::shape::*;
+use super::sphere::*;
+use super::triangle::*;
+use super::tuple::*;
+use super::world::*;
+use std::f64::consts::PI;
+
+/// Shapes per leaf before a node is split in two.
+const LEAF_SIZE: usize = 4;
+
+/// A bounding volume hierarchy over a list of shapes, so that a ray only has to
+/// be tested against the shapes whose boxes it passes through.
+///
+/// The hierarchy stores indices into the list it was built from, so it has to be
+/// rebuilt whenever that list changes. Shapes with infinite bounds, like planes,
+/// can't be placed in a box and are always tested.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    unbounded: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum BvhNode {
+    Leaf {
+        bounds: Bounds,
+        items: Vec<usize>,
+    },
+    Branch {
+        bounds: Bounds,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl Bvh {
+    pub fn build(shapes: &[Shape]) -> Bvh {
+        let bounds: Vec<Bounds> = shapes.iter().map(|shape| shape.bounds()).collect();
+        let (mut bounded, unbounded): (Vec<usize>, Vec<usize>) =
+            (0..shapes.len()).partition(|&i| bounds[i].is_finite());
+
+        let mut bvh = Bvh {
+            nodes: vec![],
+            unbounded,
+        };
+        if !bounded.is_empty() {
+            bvh.build_node(&bounds, &mut bounded);
+        }
+
+        bvh
+    }
+
+    /// Builds the subtree over `items` and returns the index of its root node.
+    fn build_node(&mut self, bounds: &[Bounds], items: &mut [usize]) -> usize {
+        let node_bounds = items
+            .iter()
+            .fold(Bounds::empty(), |acc, &i| acc.merged(&bounds[i]));
+
+        if items.len() <= LEAF_SIZE {
+            self.nodes.push(BvhNode::Leaf {
+                bounds: node_bounds,
+                items: items.to_vec(),
+            });
+            return self.nodes.len() - 1;
+        }
+
+        // Split at the median centroid, along the axis where the centroids are most
+        // spread out
+        let mut centroids = Bounds::empty();
+        for &i in items.iter() {
+            centroids.add_point(bounds[i].centroid());
+        }
+        let axis = (0..3)
+            .max_by(|&a, &b| {
+                let extent_a = centroids.max[a] - centroids.min[a];
+                let extent_b = centroids.max[b] - centroids.min[b];
+                extent_a.partial_cmp(&extent_b).unwrap()
+            })
+            .unwrap();
+
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |&a, &b| {
+            let ca = bounds[a].centroid()[axis];
+            let cb = bounds[b].centroid()[axis];
+            ca.partial_cmp(&cb).unwrap()
+        });
+
+        // Reserve this node's slot before its children take the next ones
+        let index = self.nodes.len();
+        self.nodes.push(BvhNode::Leaf {
+            bounds: node_bounds,
+            items: vec![],
+        });
+
+        let (left_items, right_items) = items.split_at_mut(mid);
+        let left = self.build_node(bounds, left_items);
+        let right = self.build_node(bounds, right_items);
+        self.nodes[index] = BvhNode::Branch {
+            bounds: node_bounds,
+            left,
+            right,
+        };
+
+        index
+    }
+
+    /// Intersects the ray with `shapes`, which must be the list this was built from.
+    /// The hits are not sorted.
+    pub fn intersect(&self, shapes: &[Shape], ray: Ray) -> Vec<Intersection> {
+        let mut hits: Vec<Intersection> = self
+            .unbounded
+            .iter()
+            .flat_map(|&i| shapes[i].intersect(ray))
+            .collect();
+
+        if self.nodes.is_empty() {
+            return hits;
+        }
+
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            match &self.nodes[index] {
+                BvhNode::Leaf { bounds, items } => {
+                    if bounds.intersects(ray) {
+                        for &i in items {
+                            hits.extend(shapes[i].intersect(ray));
+                        }
+                    }
+                }
+                BvhNode::Branch {
+                    bounds,
+                    left,
+                    right,
+                } => {
+                    if bounds.intersects(ray) {
+                        stack.push(*left);
+                        stack.push(*right);
+                    }
+                }
+            }
+        }
+
+        hits
+    }
+}
+
+#[test]
+fn bvh_finds_same_hits_as_brute_force() {
+    let shapes: Vec<Shape> = (0..50)
+        .map(|i| {
+            let x = (i % 10) as f64 * 2.5 - 12.0;
+            let y = (i / 10) as f64 * 2.5 - 6.0;
+            Sphere::default()
+                .with_transform(Matrix::translation(x, y, 0.0))
+                .into()
+        })
+        .collect();
+    let bvh = Bvh::build(&shapes);
+
+    for i in 0..50 {
+        let ray = Ray::new(
+            point(i as f64 * 0.5 - 12.0, i as f64 * 0.25 - 6.0, -10.0),
+            vector(0.01 * i as f64, 0.0, 1.0),
+        );
+        let mut expected: Vec<f64> = shapes
+            .iter()
+            .flat_map(|s| s.intersect(ray))
+            .map(|h| h.t)
+            .collect();
+        let mut actual: Vec<f64> = bvh.intersect(&shapes, ray).iter().map(|h| h.t).collect();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        actual.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(actual, expected);
+    }
+}
+
+#[test]
+fn large_triangle_mesh_renders_same_with_bvh() {
+    // A 224 x 224 grid of quads, each split in two, gives just over 100k triangles
+    // of a rippled surface.
+    let n = 224;
+    let height = |i: usize, j: usize| {
+        let (x, z) = (i as f64 / n as f64, j as f64 / n as f64);
+        0.1 * (x * 4.0 * PI).sin() * (z * 4.0 * PI).cos()
+    };
+    let vertex = |i: usize, j: usize| {
+        point(
+            i as f64 / n as f64 * 4.0 - 2.0,
+            height(i, j),
+            j as f64 / n as f64 * 4.0 - 2.0,
+        )
+    };
+
+    let mut world = World::default();
+    *world.objects_mut() = vec![Sphere::default()
+        .with_transform(
+            Matrix::identity()
+                .scale(0.5, 0.5, 0.5)
+                .translate(0.0, 1.0, 0.0),
+        )
+        .into()];
+    for i in 0..n {
+        for j in 0..n {
+            let (a, b) = (vertex(i, j), vertex(i + 1, j));
+            let (c, d) = (vertex(i + 1, j + 1), vertex(i, j + 1));
+            world.add_object(Triangle::new(a, b, c));
+            world.add_object(Triangle::new(a, c, d));
+        }
+    }
+    assert!(world.objects().len() > 100_000);
+
+    let mut camera = Camera::new(5, 5, PI / 3.0);
+    camera.set_transform(Matrix::view(
+        point(0.0, 2.5, -4.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+
+    let brute_force = camera.render(&world);
+    world.build_bvh();
+    let with_bvh = camera.render(&world);
+
+    for y in 0..5 {
+        for x in 0..5 {
+            assert_eq!(with_bvh.pixel_at(x, y), brute_force.pixel_at(x, y));
+        }
+    }
+}
@@ -1,11 +1,32 @@
 #![allow(unused_imports)]
 use super::canvas::*;
 use super::color::*;
+use super::integrator::*;
 use super::matrix::*;
 use super::ray::*;
+use super::rng::*;
+use super::sampling::*;
+use super::transform::*;
 use super::tuple::*;
 use super::world::*;
 use std::f64::consts::PI;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Width and height in pixels of the square tiles handed out to render threads.
+const TILE_SIZE: usize = 16;
+
+/// How the camera maps pixels to rays.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    /// Rays fan out from the eye through the field of view.
+    Perspective,
+    /// Parallel rays over a view `width` in world units.
+    Orthographic { width: f64 },
+    /// The full sphere around the eye, with longitude across the image and
+    /// latitude down it.
+    Equirectangular,
+}
 
 #[derive(Debug, Copy, Clone)]
 pub struct Camera {
@@ -14,7 +35,14 @@ pub struct Camera {
     field_of_view: f64,
     half_width: f64,
     half_height: f64,
-    pub transform: Matrix<4>,
+    projection: Projection,
+    transform: Transform,
+    threads: usize,
+    sampling: Sampling,
+    integrator: Integrator,
+    seed: u64,
+    aperture: f64,
+    focal_distance: f64,
 }
 
 impl Camera {
@@ -37,42 +65,341 @@ impl Camera {
             field_of_view,
             half_width,
             half_height,
-            transform: Matrix::identity(),
+            projection: Projection::Perspective,
+            transform: Transform::identity(),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            sampling: Sampling::Single,
+            integrator: Integrator::Whitted,
+            seed: 0,
+            aperture: 0.0,
+            focal_distance: 1.0,
+        }
+    }
+
+    /// A camera with parallel rays, seeing `width` world units across.
+    pub fn orthographic(hsize: usize, vsize: usize, width: f64) -> Self {
+        let half_width = width / 2.0;
+
+        Camera {
+            half_width,
+            half_height: half_width * vsize as f64 / hsize as f64,
+            projection: Projection::Orthographic { width },
+            ..Camera::new(hsize, vsize, PI / 2.0)
+        }
+    }
+
+    /// A 360° camera whose image covers every direction around it.
+    pub fn equirectangular(hsize: usize, vsize: usize) -> Self {
+        Camera {
+            projection: Projection::Equirectangular,
+            ..Camera::new(hsize, vsize, PI / 2.0)
         }
     }
 
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    /// The same camera rendering an image of a different size, seeing as much
+    /// of the scene across as before.
+    pub fn with_size(self, hsize: usize, vsize: usize) -> Self {
+        let resized = match self.projection {
+            Projection::Perspective => Camera::new(hsize, vsize, self.field_of_view),
+            Projection::Orthographic { width } => Camera::orthographic(hsize, vsize, width),
+            Projection::Equirectangular => Camera::equirectangular(hsize, vsize),
+        };
+
+        Camera {
+            hsize,
+            vsize,
+            half_width: resized.half_width,
+            half_height: resized.half_height,
+            ..self
+        }
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// Renders with `threads` worker threads; 1 shades every pixel on the
+    /// calling thread.
+    pub fn with_threads(self, threads: usize) -> Self {
+        Camera {
+            threads: threads.max(1),
+            ..self
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn with_sampling(self, sampling: Sampling) -> Self {
+        Camera { sampling, ..self }
+    }
+
+    /// Shades with `integrator` instead of the default Whitted shading.
+    pub fn with_integrator(self, integrator: Integrator) -> Self {
+        Camera { integrator, ..self }
+    }
+
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    /// Seeds the jitter used by sampling, so the same seed always renders the
+    /// same image.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Camera { seed, ..self }
+    }
+
+    /// Turns the pinhole into a thin lens `aperture` wide, keeping things
+    /// `focal_distance` in front of the camera in focus. An aperture of zero
+    /// is a pinhole again. Only perspective cameras have a lens.
+    pub fn with_depth_of_field(self, aperture: f64, focal_distance: f64) -> Self {
+        Camera {
+            aperture,
+            focal_distance,
+            ..self
+        }
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    /// Sets the view transform, caching its inverse for `ray_for_pixel`.
+    pub fn set_transform(&mut self, transform: Matrix<4>) {
+        self.transform = Transform::new(transform);
+    }
+
     pub fn pixel_size(&self) -> f64 {
         self.half_width * 2.0 / self.hsize as f64
     }
 
     pub fn ray_for_pixel(self, px: &usize, py: &usize) -> Ray {
-        // Calculate the offset from the edge of the canvas to the pixel's center.
-        let x_offset = (*px as f64 + 0.5) * self.pixel_size();
-        let y_offset = (*py as f64 + 0.5) * self.pixel_size();
+        self.ray_for_subpixel(*px, *py, 0.5, 0.5)
+    }
+
+    /// A ray through the point `(dx, dy)` inside the pixel, where `(0, 0)` is
+    /// its top left corner and `(0.5, 0.5)` its center.
+    pub fn ray_for_subpixel(&self, px: usize, py: usize, dx: f64, dy: f64) -> Ray {
+        match self.projection {
+            Projection::Perspective => self.perspective_ray(px, py, dx, dy),
+            Projection::Orthographic { .. } => self.orthographic_ray(px, py, dx, dy),
+            Projection::Equirectangular => self.equirectangular_ray(px, py, dx, dy),
+        }
+    }
+
+    fn perspective_ray(&self, px: usize, py: usize, dx: f64, dy: f64) -> Ray {
+        // Calculate the offset from the edge of the canvas to the sample point.
+        let x_offset = (px as f64 + dx) * self.pixel_size();
+        let y_offset = (py as f64 + dy) * self.pixel_size();
 
         let world_x = self.half_width - x_offset;
         let world_y = self.half_height - y_offset;
 
-        let pixel = self.transform.inverse() * point(world_x, world_y, -1.0);
-        let origin = self.transform.inverse() * point(0.0, 0.0, 0.0);
+        // Rays from anywhere on the lens meet the pinhole ray on the focal
+        // plane, so only things at that distance stay sharp.
+        let (lens, focus) = if self.aperture > 0.0 {
+            let mut rng = Rng::from_values(self.seed, &[px as f64 + dx, py as f64 + dy]);
+            let radius = self.aperture / 2.0 * rng.next_f64().sqrt();
+            let theta = 2.0 * PI * rng.next_f64();
+            (
+                point(radius * theta.cos(), radius * theta.sin(), 0.0),
+                self.focal_distance,
+            )
+        } else {
+            (point(0.0, 0.0, 0.0), 1.0)
+        };
+
+        let inverse = self.transform.inverse();
+        let pixel = inverse * point(world_x * focus, world_y * focus, -focus);
+        let origin = inverse * lens;
         let direction = (pixel - origin).normalized();
 
         Ray::new(origin, direction)
     }
 
+    fn orthographic_ray(&self, px: usize, py: usize, dx: f64, dy: f64) -> Ray {
+        let world_x = self.half_width - (px as f64 + dx) * self.pixel_size();
+        let world_y = self.half_height - (py as f64 + dy) * self.pixel_size();
+
+        let inverse = self.transform.inverse();
+        let origin = inverse * point(world_x, world_y, 0.0);
+        let direction = inverse * vector(0.0, 0.0, -1.0);
+
+        Ray::new(origin, direction)
+    }
+
+    fn equirectangular_ray(&self, px: usize, py: usize, dx: f64, dy: f64) -> Ray {
+        let u = (px as f64 + dx) / self.hsize as f64;
+        let v = (py as f64 + dy) / self.vsize as f64;
+        let longitude = (u - 0.5) * 2.0 * PI;
+        let latitude = (0.5 - v) * PI;
+
+        // The image center looks down -z, and like the perspective camera the
+        // left edge of the image is toward +x in camera space.
+        let local = vector(
+            -longitude.sin() * latitude.cos(),
+            latitude.sin(),
+            -longitude.cos() * latitude.cos(),
+        );
+
+        let inverse = self.transform.inverse();
+        Ray::new(inverse * point(0.0, 0.0, 0.0), inverse * local)
+    }
+
     pub fn render(&self, world: &World) -> Canvas {
+        self.render_with_progress(world, |_, _| {})
+    }
+
+    /// Renders like `render`, calling `progress` with the number of pixels
+    /// done so far and the total as rows or tiles complete. With several
+    /// threads it is called from whichever thread finished the work.
+    pub fn render_with_progress(
+        &self,
+        world: &World,
+        progress: impl Fn(usize, usize) + Sync,
+    ) -> Canvas {
         let mut image = Canvas::new(self.hsize, self.vsize);
+        let total = self.hsize * self.vsize;
+
+        if self.threads == 1 {
+            for y in 0..self.vsize {
+                for x in 0..self.hsize {
+                    image.write(x, y, self.pixel_color(world, x, y));
+                }
+                progress((y + 1) * self.hsize, total);
+            }
+            return image;
+        }
+
+        let tiles = self.tiles();
+        let next = AtomicUsize::new(0);
+        let done_pixels = AtomicUsize::new(0);
+        let rendered: Vec<(Tile, Vec<Color>)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads.min(tiles.len()))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(&tile) = tiles.get(index) else {
+                                break;
+                            };
+                            done.push((tile, self.render_tile(world, tile)));
 
-        for y in 0..self.vsize - 1 {
-            for x in 0..self.hsize - 1 {
-                let ray = self.ray_for_pixel(&x, &y);
-                let color = world.color(&ray);
-                image.write(x, y, color);
+                            let pixels = tile.width * tile.height;
+                            let so_far = done_pixels.fetch_add(pixels, Ordering::Relaxed) + pixels;
+                            progress(so_far, total);
+                        }
+                        done
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        });
+
+        for (tile, colors) in rendered {
+            let mut colors = colors.into_iter();
+            for y in tile.y..tile.y + tile.height {
+                for x in tile.x..tile.x + tile.width {
+                    image.write(x, y, colors.next().unwrap());
+                }
             }
         }
 
         image
     }
+
+    fn pixel_color(&self, world: &World, x: usize, y: usize) -> Color {
+        // Seeding per pixel keeps the jitter independent of which thread, and
+        // in which order, the pixel gets rendered.
+        let mut rng = Rng::from_values(self.seed, &[x as f64, y as f64]);
+
+        let offsets = match self.sampling {
+            Sampling::Single => vec![(0.5, 0.5)],
+            Sampling::Grid(n) => subpixel_offsets(n, None),
+            Sampling::Jittered(n) => subpixel_offsets(n, Some(&mut rng)),
+            Sampling::Adaptive { samples, threshold } => {
+                let first = subpixel_offsets(2, Some(&mut rng));
+                let mut colors = self.sample(world, x, y, &first, &mut rng);
+                if colors_differ(&colors, threshold) {
+                    let refined = subpixel_offsets(samples, Some(&mut rng));
+                    colors.extend(self.sample(world, x, y, &refined, &mut rng));
+                }
+                return average(&colors);
+            }
+        };
+
+        average(&self.sample(world, x, y, &offsets, &mut rng))
+    }
+
+    fn sample(
+        &self,
+        world: &World,
+        x: usize,
+        y: usize,
+        offsets: &[(f64, f64)],
+        rng: &mut Rng,
+    ) -> Vec<Color> {
+        offsets
+            .iter()
+            .map(|&(dx, dy)| {
+                let ray = self.ray_for_subpixel(x, y, dx, dy);
+                self.integrator.color(world, &ray, rng)
+            })
+            .collect()
+    }
+
+    /// Splits the image into tiles of at most `TILE_SIZE` square, row by row.
+    fn tiles(&self) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        for y in (0..self.vsize).step_by(TILE_SIZE) {
+            for x in (0..self.hsize).step_by(TILE_SIZE) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: TILE_SIZE.min(self.hsize - x),
+                    height: TILE_SIZE.min(self.vsize - y),
+                });
+            }
+        }
+        tiles
+    }
+
+    /// Shades one tile, returning its colors in row-major order.
+    fn render_tile(&self, world: &World, tile: Tile) -> Vec<Color> {
+        let mut colors = Vec::with_capacity(tile.width * tile.height);
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                colors.push(self.pixel_color(world, x, y));
+            }
+        }
+        colors
+    }
+}
+
+fn average(colors: &[Color]) -> Color {
+    colors.iter().fold(Color::black(), |sum, &c| sum + c) / colors.len() as f64
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Tile {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
 }
 
 #[test]
@@ -93,7 +420,7 @@ fn ray_through_center_of_canvas() {
 #[test]
 fn ray_when_camera_is_transformed() {
     let mut c = Camera::new(201, 101, PI / 2.0);
-    c.transform = Matrix::rotation_y(PI / 4.0) * Matrix::translation(0.0, -2.0, 5.0);
+    c.set_transform(Matrix::rotation_y(PI / 4.0) * Matrix::translation(0.0, -2.0, 5.0));
     let ray = c.ray_for_pixel(&100, &50);
 
     assert_eq!(ray.origin, point(0.0, 2.0, -5.0));
@@ -110,8 +437,280 @@ fn render_world_with_camera() {
     let from = point(0.0, 0.0, -5.0);
     let to = point(0.0, 0.0, 0.0);
     let up = vector(0.0, 1.0, 0.0);
-    c.transform = Matrix::view(from, to, up);
+    c.set_transform(Matrix::view(from, to, up));
     let image = c.render(&w);
 
     assert_eq!(image.pixel_at(5, 5), color(0.38066, 0.47583, 0.2855));
 }
+
+#[test]
+fn render_fills_last_row_and_column() {
+    let w = World::default();
+    let mut c = Camera::new(11, 11, PI / 2.0).with_threads(1);
+    c.set_transform(Matrix::view(
+        point(0.0, 0.0, -1.2),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    let image = c.render(&w);
+
+    assert_ne!(image.pixel_at(10, 5), Color::black());
+    assert_ne!(image.pixel_at(5, 10), Color::black());
+}
+
+#[test]
+fn threaded_render_matches_serial_render() {
+    let w = World::default();
+    let mut c = Camera::new(37, 21, PI / 2.0);
+    c.set_transform(Matrix::view(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    let serial = c.with_threads(1).render(&w);
+    let threaded = c.with_threads(4).render(&w);
+
+    for y in 0..21 {
+        for x in 0..37 {
+            let (a, b) = (serial.pixel_at(x, y), threaded.pixel_at(x, y));
+            assert_eq!((a.r, a.g, a.b), (b.r, b.g, b.b));
+        }
+    }
+}
+
+#[test]
+fn ray_for_subpixel_at_center_matches_ray_for_pixel() {
+    let c = Camera::new(201, 101, PI / 2.0);
+
+    assert_eq!(
+        c.ray_for_subpixel(100, 50, 0.5, 0.5),
+        c.ray_for_pixel(&100, &50)
+    );
+}
+
+#[test]
+fn ray_for_subpixel_at_pixel_corner() {
+    let c = Camera::new(201, 101, PI / 2.0);
+    let ray = c.ray_for_subpixel(0, 0, 0.0, 0.0);
+    let expected = vector(1.0, 101.0 / 201.0, -1.0).normalized();
+
+    assert_eq!(ray.direction, expected);
+}
+
+#[cfg(test)]
+fn sampled_camera(sampling: Sampling) -> Camera {
+    let mut c = Camera::new(11, 11, PI / 2.0)
+        .with_threads(1)
+        .with_sampling(sampling);
+    c.set_transform(Matrix::view(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    c
+}
+
+#[test]
+fn one_by_one_grid_matches_single_sample() {
+    let w = World::default();
+    let single = sampled_camera(Sampling::Single).render(&w);
+    let grid = sampled_camera(Sampling::Grid(1)).render(&w);
+
+    for y in 0..11 {
+        for x in 0..11 {
+            assert_eq!(single.pixel_at(x, y), grid.pixel_at(x, y));
+        }
+    }
+}
+
+#[test]
+fn supersampling_softens_edges() {
+    let w = World::default();
+    let single = sampled_camera(Sampling::Single).render(&w);
+    let grid = sampled_camera(Sampling::Grid(4)).render(&w);
+
+    // Some pixel on the sphere's silhouette misses it through its center,
+    // but picks up part of it from the other samples.
+    let softened = (0..11)
+        .flat_map(|y| (0..11).map(move |x| (x, y)))
+        .any(|(x, y)| {
+            single.pixel_at(x, y) == Color::black() && grid.pixel_at(x, y) != Color::black()
+        });
+    assert!(softened);
+    assert_eq!(grid.pixel_at(0, 0), Color::black());
+}
+
+#[test]
+fn jittered_render_is_reproducible_for_a_seed() {
+    let w = World::default();
+    let a = sampled_camera(Sampling::Jittered(2))
+        .with_seed(7)
+        .render(&w);
+    let b = sampled_camera(Sampling::Jittered(2))
+        .with_seed(7)
+        .with_threads(3)
+        .render(&w);
+
+    for y in 0..11 {
+        for x in 0..11 {
+            let (a, b) = (a.pixel_at(x, y), b.pixel_at(x, y));
+            assert_eq!((a.r, a.g, a.b), (b.r, b.g, b.b));
+        }
+    }
+}
+
+#[test]
+fn adaptive_sampling_refines_only_where_samples_differ() {
+    let w = World::default();
+    let coarse = sampled_camera(Sampling::Jittered(2)).render(&w);
+    let adaptive = |threshold| {
+        sampled_camera(Sampling::Adaptive {
+            samples: 4,
+            threshold,
+        })
+        .render(&w)
+    };
+
+    // Past any possible difference nothing is refined, leaving the jittered
+    // 2x2 pass as is.
+    let unrefined = adaptive(10.0);
+    let refined = adaptive(0.01);
+    let mut changed = 0;
+    for y in 0..11 {
+        for x in 0..11 {
+            let (a, b) = (coarse.pixel_at(x, y), unrefined.pixel_at(x, y));
+            assert_eq!((a.r, a.g, a.b), (b.r, b.g, b.b));
+            if refined.pixel_at(x, y) != coarse.pixel_at(x, y) {
+                changed += 1;
+            }
+        }
+    }
+    assert!(changed > 0);
+    assert_eq!(refined.pixel_at(0, 0), Color::black());
+}
+
+#[test]
+fn lens_rays_converge_on_the_focal_plane() {
+    let pinhole = Camera::new(11, 11, PI / 2.0);
+    let lens = pinhole.with_depth_of_field(0.5, 4.0);
+
+    for (dx, dy) in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.3)] {
+        let through = pinhole.ray_for_subpixel(3, 7, dx, dy);
+        let focus = through.position(4.0 / -through.direction.z);
+
+        let ray = lens.ray_for_subpixel(3, 7, dx, dy);
+        assert_eq!(ray.origin.z, 0.0);
+        assert!((ray.origin - point(0.0, 0.0, 0.0)).magnitude() <= 0.25);
+        assert_eq!(ray.position(4.0 / -ray.direction.z), focus);
+    }
+}
+
+#[test]
+fn lens_rays_start_at_different_points_on_the_lens() {
+    let c = Camera::new(11, 11, PI / 2.0).with_depth_of_field(0.5, 4.0);
+    let a = c.ray_for_subpixel(3, 7, 0.25, 0.25);
+    let b = c.ray_for_subpixel(3, 7, 0.75, 0.75);
+
+    assert_ne!(a.origin, b.origin);
+    assert_eq!(a, c.ray_for_subpixel(3, 7, 0.25, 0.25));
+}
+
+#[test]
+fn zero_aperture_is_a_pinhole() {
+    let c = Camera::new(201, 101, PI / 2.0).with_depth_of_field(0.0, 4.0);
+
+    assert_eq!(
+        c.ray_for_pixel(&0, &0),
+        Camera::new(201, 101, PI / 2.0).ray_for_pixel(&0, &0)
+    );
+}
+
+#[test]
+fn orthographic_rays_are_parallel() {
+    let c = Camera::orthographic(200, 100, 4.0);
+
+    let corner = c.ray_for_subpixel(0, 0, 0.0, 0.0);
+    assert_eq!(corner.origin, point(2.0, 1.0, 0.0));
+    assert_eq!(corner.direction, vector(0.0, 0.0, -1.0));
+
+    let center = c.ray_for_pixel(&100, &50);
+    assert_eq!(center.origin, point(-0.01, -0.01, 0.0));
+    assert_eq!(center.direction, vector(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn orthographic_ray_when_camera_is_transformed() {
+    let mut c = Camera::orthographic(200, 100, 4.0);
+    c.set_transform(Matrix::view(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    let ray = c.ray_for_subpixel(0, 0, 0.0, 0.0);
+
+    assert_eq!(ray.origin, point(-2.0, 1.0, -5.0));
+    assert_eq!(ray.direction, vector(0.0, 0.0, 1.0));
+}
+
+#[test]
+fn equirectangular_rays_cover_the_sphere() {
+    let c = Camera::equirectangular(360, 180);
+
+    let center = c.ray_for_subpixel(180, 90, 0.0, 0.0);
+    assert_eq!(center.origin, point(0.0, 0.0, 0.0));
+    assert_eq!(center.direction, vector(0.0, 0.0, -1.0));
+
+    let behind = c.ray_for_subpixel(0, 90, 0.0, 0.0);
+    assert_eq!(behind.direction, vector(0.0, 0.0, 1.0));
+
+    let quarter = c.ray_for_subpixel(90, 90, 0.0, 0.0);
+    assert_eq!(quarter.direction, vector(1.0, 0.0, 0.0));
+
+    let up = c.ray_for_subpixel(180, 0, 0.0, 0.0);
+    assert_eq!(up.direction, vector(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn equirectangular_ray_when_camera_is_transformed() {
+    let mut c = Camera::equirectangular(360, 180);
+    c.set_transform(Matrix::view(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+    let ray = c.ray_for_subpixel(180, 90, 0.0, 0.0);
+
+    assert_eq!(ray.origin, point(0.0, 0.0, -5.0));
+    assert_eq!(ray.direction, vector(0.0, 0.0, 1.0));
+}
+
+#[test]
+fn resized_camera_keeps_its_settings() {
+    let mut c = Camera::new(201, 101, PI / 2.0).with_threads(2);
+    c.set_transform(Matrix::translation(0.0, -2.0, 5.0));
+    let resized = c.with_size(402, 202);
+
+    assert_eq!((resized.hsize(), resized.vsize()), (402, 202));
+    assert_eq!(resized.threads(), 2);
+    assert_eq!(resized.transform(), c.transform());
+    assert_eq!(
+        resized.ray_for_subpixel(0, 0, 0.0, 0.0),
+        c.ray_for_subpixel(0, 0, 0.0, 0.0)
+    );
+}
+
+#[test]
+fn render_reports_progress_up_to_every_pixel() {
+    let w = World::default();
+    let c = Camera::new(20, 18, PI / 2.0);
+
+    for threads in [1, 3] {
+        let done = AtomicUsize::new(0);
+        c.with_threads(threads)
+            .render_with_progress(&w, |so_far, total| {
+                assert_eq!(total, 20 * 18);
+                done.fetch_max(so_far, Ordering::Relaxed);
+            });
+        assert_eq!(done.into_inner(), 20 * 18);
+    }
+}
@@ -1,6 +1,13 @@
 use crate::rz::color::*;
-use image::{ImageBuffer, Rgb, RgbImage};
+use crate::rz::tonemap::*;
+use image::codecs::hdr::HdrEncoder;
+use image::{ImageBuffer, ImageResult, Rgb, Rgb32FImage, RgbImage};
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
@@ -17,7 +24,7 @@ impl Canvas {
         }
     }
 
-    pub fn pixel_at(self, x: usize, y: usize) -> Color {
+    pub fn pixel_at(&self, x: usize, y: usize) -> Color {
         self.pixels[x + y * self.width]
     }
 
@@ -25,22 +32,131 @@ impl Canvas {
         self.pixels[x + y * self.width] = color;
     }
 
-    pub fn save(&self, file: &str) {
-        let mut image: RgbImage = ImageBuffer::new(self.width as u32, self.height as u32);
-
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let index = y * self.width + x;
-                let color = self.pixels[index];
-                let pixel = Rgb([
-                    (color.r * 255.0) as u8,
-                    (color.g * 255.0) as u8,
-                    (color.b * 255.0) as u8,
-                ]);
-                image.put_pixel(x as u32, y as u32, pixel)
-            }
+    /// Reads a PPM file, or an image in any other format the `image` crate
+    /// understands, with channels scaled to 0.0–1.0.
+    pub fn load(file: &str) -> ImageResult<Canvas> {
+        if extension(file).as_deref() == Some("ppm") {
+            return Ok(Canvas::from_ppm(&fs::read(file)?)?);
         }
 
-        image.save(file).unwrap();
+        let image = image::open(file)?.into_rgb8();
+        let mut canvas = Canvas::new(image.width() as usize, image.height() as usize);
+
+        for (x, y, pixel) in image.enumerate_pixels() {
+            let [r, g, b] = pixel.0;
+            canvas.write(
+                x as usize,
+                y as usize,
+                Color::new(r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0),
+            );
+        }
+
+        Ok(canvas)
+    }
+
+    /// Saves with the default tone mapping; see `save_with`.
+    pub fn save(&self, file: &str) -> ImageResult<()> {
+        self.save_with(file, &ToneMapping::default())
+    }
+
+    /// Saves the canvas in the format its extension names. `.hdr` (Radiance)
+    /// and `.exr` (OpenEXR) files keep the linear colors as they are, anything
+    /// else goes through `tone_mapping` down to 8 bits per channel. `.ppm`
+    /// files are written as binary `P6`.
+    pub fn save_with(&self, file: &str, tone_mapping: &ToneMapping) -> ImageResult<()> {
+        match extension(file).as_deref() {
+            Some("hdr") => self.save_hdr(file),
+            Some("exr") => self.to_rgb32f().save(file),
+            Some("ppm") => Ok(fs::write(file, self.to_ppm_binary(tone_mapping))?),
+            _ => self.to_rgb8(tone_mapping).save(file),
+        }
+    }
+
+    fn save_hdr(&self, file: &str) -> ImageResult<()> {
+        let pixels: Vec<Rgb<f32>> = self.to_rgb32f().pixels().copied().collect();
+        let writer = BufWriter::new(File::create(file)?);
+
+        HdrEncoder::new(writer).encode(&pixels, self.width, self.height)
+    }
+
+    fn to_rgb32f(&self) -> Rgb32FImage {
+        ImageBuffer::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let color = self.pixel_at(x as usize, y as usize);
+            Rgb([color.r as f32, color.g as f32, color.b as f32])
+        })
+    }
+
+    fn to_rgb8(&self, tone_mapping: &ToneMapping) -> RgbImage {
+        ImageBuffer::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let color = tone_mapping.apply(self.pixel_at(x as usize, y as usize));
+            Rgb([
+                (color.r * 255.0).round() as u8,
+                (color.g * 255.0).round() as u8,
+                (color.b * 255.0).round() as u8,
+            ])
+        })
     }
 }
+
+fn extension(file: &str) -> Option<String> {
+    Path::new(file)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+}
+
+#[test]
+fn saving_and_loading_ppm_and_png() {
+    let mut canvas = Canvas::new(3, 2);
+    canvas.write(0, 0, Color::new(1.0, 0.0, 0.2));
+    canvas.write(2, 1, Color::new(0.4, 0.6, 0.8));
+
+    for extension in ["ppm", "png"] {
+        let file = std::env::temp_dir().join(format!("rz-canvas-load.{}", extension));
+        let file = file.to_string_lossy();
+        canvas.save(&file).unwrap();
+
+        assert_eq!(Canvas::load(&file).unwrap(), canvas);
+    }
+}
+
+#[test]
+fn saving_tone_maps_ldr_images() {
+    let mut canvas = Canvas::new(2, 1);
+    canvas.write(0, 0, Color::new(0.5, 2.0, 0.0));
+    canvas.write(1, 0, Color::new(1.0, 1.0, 1.0));
+    let file = std::env::temp_dir().join("rz-canvas-tonemap.png");
+    let file = file.to_string_lossy();
+
+    canvas.save(&file).unwrap();
+    let image = image::open(&*file).unwrap().into_rgb8();
+    assert_eq!(image.get_pixel(0, 0).0, [128, 255, 0]);
+
+    let reinhard = ToneMapping::new(ToneMap::Reinhard);
+    canvas.save_with(&file, &reinhard).unwrap();
+    let image = image::open(&*file).unwrap().into_rgb8();
+    assert_eq!(image.get_pixel(0, 0).0, [85, 170, 0]);
+    assert_eq!(image.get_pixel(1, 0).0, [128, 128, 128]);
+}
+
+#[test]
+fn hdr_formats_keep_values_above_one() {
+    let mut canvas = Canvas::new(2, 1);
+    canvas.write(0, 0, Color::new(4.0, 0.5, 0.25));
+    canvas.write(1, 0, Color::new(0.0, 16.0, 1.0));
+
+    let file = std::env::temp_dir().join("rz-canvas-linear.hdr");
+    canvas.save(&file.to_string_lossy()).unwrap();
+    let reader = std::io::BufReader::new(File::open(&file).unwrap());
+    let pixels = image::codecs::hdr::HdrDecoder::new(reader)
+        .unwrap()
+        .read_image_hdr()
+        .unwrap();
+    assert_eq!(pixels[0].0, [4.0, 0.5, 0.25]);
+    assert_eq!(pixels[1].0, [0.0, 16.0, 1.0]);
+
+    let file = std::env::temp_dir().join("rz-canvas-linear.exr");
+    canvas.save(&file.to_string_lossy()).unwrap();
+    let image = image::open(&file).unwrap().into_rgb32f();
+    assert_eq!(image.get_pixel(0, 0).0, [4.0, 0.5, 0.25]);
+    assert_eq!(image.get_pixel(1, 0).0, [0.0, 16.0, 1.0]);
+}
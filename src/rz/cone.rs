@@ -0,0 +1,215 @@
+#![allow(unused_imports)]
+use super::bounds::*;
+use super::cylinder::check_cap;
+use super::intersection::*;
+use super::material::*;
+use super::matrix::*;
+use super::ray::*;
+use super::shape::*;
+use super::transform::*;
+use super::tuple::*;
+const EPSILON: f64 = 0.00001;
+
+/// A double-napped cone around the y axis, with its tip at the origin and a
+/// radius equal to |y|. Truncation and caps work as they do for `Cylinder`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cone {
+    pub transform: Transform,
+    pub material: Material,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+
+impl From<Cone> for Shape {
+    fn from(cone: Cone) -> Self {
+        Shape::Cone(cone)
+    }
+}
+
+impl Cone {
+    pub fn new(transform: Matrix<4>, material: Material) -> Self {
+        Self {
+            transform: transform.into(),
+            material,
+            ..Self::default()
+        }
+    }
+
+    pub fn default() -> Self {
+        Self {
+            transform: Transform::identity(),
+            material: Material::default_material(),
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+
+    pub fn normal(&self, p: Tuple) -> Tuple {
+        let dist = p.x * p.x + p.z * p.z;
+
+        if dist < self.maximum * self.maximum && p.y >= self.maximum - EPSILON {
+            vector(0.0, 1.0, 0.0)
+        } else if dist < self.minimum * self.minimum && p.y <= self.minimum + EPSILON {
+            vector(0.0, -1.0, 0.0)
+        } else {
+            let y = dist.sqrt();
+            vector(p.x, if p.y > 0.0 { -y } else { y }, p.z)
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        let r = self.minimum.abs().max(self.maximum.abs());
+        Bounds::new(point(-r, self.minimum, -r), point(r, self.maximum, r))
+    }
+
+    pub fn with_transform(&self, transform: impl Into<Transform>) -> Self {
+        Self {
+            transform: transform.into(),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_material(&self, material: Material) -> Self {
+        Self {
+            material,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_bounds(&self, minimum: f64, maximum: f64, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+            ..self.clone()
+        }
+    }
+
+    pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+        let mut hits = vec![];
+        let (o, d) = (ray.origin, ray.direction);
+
+        let a = d.x * d.x - d.y * d.y + d.z * d.z;
+        let b = 2.0 * o.x * d.x - 2.0 * o.y * d.y + 2.0 * o.z * d.z;
+        let c = o.x * o.x - o.y * o.y + o.z * o.z;
+
+        if a.abs() < EPSILON {
+            // The ray is parallel to one of the halves, so it can only cross the other
+            if b.abs() >= EPSILON {
+                let t = -c / (2.0 * b);
+                self.push_if_in_bounds(ray, t, &mut hits);
+            }
+        } else {
+            let det = b * b - 4.0 * a * c;
+            if det < 0.0 {
+                return vec![];
+            }
+
+            let t0 = (-b - det.sqrt()) / (2.0 * a);
+            let t1 = (-b + det.sqrt()) / (2.0 * a);
+
+            self.push_if_in_bounds(ray, t0.min(t1), &mut hits);
+            self.push_if_in_bounds(ray, t0.max(t1), &mut hits);
+        }
+
+        self.intersect_caps(ray, &mut hits);
+        hits
+    }
+
+    fn push_if_in_bounds(&self, ray: Ray, t: f64, hits: &mut Vec<Intersection>) {
+        let y = ray.origin.y + t * ray.direction.y;
+        if self.minimum < y && y < self.maximum {
+            hits.push(Intersection::new(t, self.clone().into()));
+        }
+    }
+
+    fn intersect_caps(&self, ray: Ray, hits: &mut Vec<Intersection>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, y.abs()) {
+                hits.push(Intersection::new(t, self.clone().into()));
+            }
+        }
+    }
+}
+
+#[test]
+fn intersecting_a_cone_with_a_ray() {
+    let obj: Shape = Cone::default().into();
+    let cases = [
+        (point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 5.0, 5.0),
+        (
+            point(0.0, 0.0, -5.0),
+            vector(1.0, 1.0, 1.0),
+            8.66025,
+            8.66025,
+        ),
+        (
+            point(1.0, 1.0, -5.0),
+            vector(-0.5, -1.0, 1.0),
+            4.55006,
+            49.44994,
+        ),
+    ];
+
+    for (origin, direction, t0, t1) in cases {
+        let hits = obj.intersect(Ray::new(origin, direction));
+
+        assert_eq!(hits.len(), 2);
+        assert!((hits[0].t - t0).abs() < 0.0001);
+        assert!((hits[1].t - t1).abs() < 0.0001);
+    }
+}
+
+#[test]
+fn intersecting_a_cone_with_ray_parallel_to_one_half() {
+    let obj: Shape = Cone::default().into();
+    let hits = obj.intersect(Ray::new(point(0.0, 0.0, -1.0), vector(0.0, 1.0, 1.0)));
+
+    assert_eq!(hits.len(), 1);
+    assert!((hits[0].t - 0.35355).abs() < EPSILON);
+}
+
+#[test]
+fn intersecting_the_caps_of_a_closed_cone() {
+    let obj: Shape = Cone::default().with_bounds(-0.5, 0.5, true).into();
+    let cases = [
+        (point(0.0, 0.0, -5.0), vector(0.0, 1.0, 0.0), 0),
+        (point(0.0, 0.0, -0.25), vector(0.0, 1.0, 1.0), 2),
+        (point(0.0, 0.0, -0.25), vector(0.0, 1.0, 0.0), 4),
+    ];
+
+    for (origin, direction, count) in cases {
+        let hits = obj.intersect(Ray::new(origin, direction));
+        assert_eq!(hits.len(), count);
+    }
+}
+
+#[test]
+fn normal_on_a_cone() {
+    let cone = Cone::default();
+    let sq2 = 2_f64.sqrt();
+    let cases = [
+        (point(0.0, 0.0, 0.0), vector(0.0, 0.0, 0.0)),
+        (point(1.0, 1.0, 1.0), vector(1.0, -sq2, 1.0)),
+        (point(-1.0, -1.0, 0.0), vector(-1.0, 1.0, 0.0)),
+    ];
+
+    for (p, expected) in cases {
+        assert_eq!(cone.normal(p), expected);
+    }
+}
+
+#[test]
+fn normal_on_cone_end_caps() {
+    let cone = Cone::default().with_bounds(-1.0, 2.0, true);
+
+    assert_eq!(cone.normal(point(0.5, 2.0, 0.0)), vector(0.0, 1.0, 0.0));
+    assert_eq!(cone.normal(point(0.5, -1.0, 0.0)), vector(0.0, -1.0, 0.0));
+}
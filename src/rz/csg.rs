@@ -0,0 +1,295 @@
+#![allow(unused_imports)]
+use super::bounds::*;
+use super::cube::*;
+use super::intersection::*;
+use super::matrix::*;
+use super::ray::*;
+use super::shape::*;
+use super::sphere::*;
+use super::transform::*;
+use super::tuple::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CsgOperation {
+    /// Everything inside either child.
+    Union,
+    /// Only what is inside both children.
+    Intersection,
+    /// What is inside the left child but not the right.
+    Difference,
+}
+
+impl CsgOperation {
+    /// Whether a hit on the surface of one child belongs to the combined shape.
+    /// `left_hit` says which child was hit, and `in_left` and `in_right` whether
+    /// the hit point is inside the left and right children.
+    pub fn allows(&self, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+            CsgOperation::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+            CsgOperation::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+        }
+    }
+}
+
+/// Constructive solid geometry: two shapes combined by a set operation.
+/// Like a group, the children's transforms are relative to the `Csg`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Csg {
+    pub operation: CsgOperation,
+    pub transform: Transform,
+    pub left: Box<Shape>,
+    pub right: Box<Shape>,
+}
+
+impl From<Csg> for Shape {
+    fn from(csg: Csg) -> Self {
+        Shape::Csg(csg)
+    }
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: impl Into<Shape>, right: impl Into<Shape>) -> Self {
+        Self {
+            operation,
+            transform: Transform::identity(),
+            left: Box::new(left.into()),
+            right: Box::new(right.into()),
+        }
+    }
+
+    pub fn union(left: impl Into<Shape>, right: impl Into<Shape>) -> Self {
+        Csg::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: impl Into<Shape>, right: impl Into<Shape>) -> Self {
+        Csg::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference(left: impl Into<Shape>, right: impl Into<Shape>) -> Self {
+        Csg::new(CsgOperation::Difference, left, right)
+    }
+
+    pub fn with_transform(self, transform: impl Into<Transform>) -> Self {
+        Self {
+            transform: transform.into(),
+            ..self
+        }
+    }
+
+    /// Builds bounding volume hierarchies for any groups among the children.
+    pub fn build_bvh(&mut self) {
+        for child in [&mut *self.left, &mut *self.right] {
+            match child {
+                Shape::Group(group) => group.build_bvh(),
+                Shape::Csg(csg) => csg.build_bvh(),
+                _ => {}
+            }
+        }
+    }
+
+    /// The box around the children, in the csg's space. Nothing outside the
+    /// left child survives a difference or an intersection.
+    pub fn bounds(&self) -> Bounds {
+        match self.operation {
+            CsgOperation::Union => self.left.bounds().merged(&self.right.bounds()),
+            CsgOperation::Intersection | CsgOperation::Difference => self.left.bounds(),
+        }
+    }
+
+    pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+        let mut hits: Vec<(Intersection, bool)> = self
+            .left
+            .intersect(ray)
+            .into_iter()
+            .map(|hit| (hit, true))
+            .chain(
+                self.right
+                    .intersect(ray)
+                    .into_iter()
+                    .map(|hit| (hit, false)),
+            )
+            .collect();
+        hits.sort_unstable_by(|a, b| a.0.t.partial_cmp(&b.0.t).unwrap());
+
+        self.filter_intersections(hits)
+    }
+
+    /// Keeps the hits that lie on the surface of the combined shape. Each hit
+    /// is paired with whether it came from the left child, and they must be
+    /// sorted by `t`.
+    pub fn filter_intersections(&self, hits: Vec<(Intersection, bool)>) -> Vec<Intersection> {
+        let mut in_left = false;
+        let mut in_right = false;
+        let mut result = vec![];
+
+        for (hit, left_hit) in hits {
+            if self.operation.allows(left_hit, in_left, in_right) {
+                result.push(hit);
+            }
+
+            if left_hit {
+                in_left = !in_left;
+            } else {
+                in_right = !in_right;
+            }
+        }
+
+        result
+    }
+}
+
+#[test]
+fn csg_is_created_with_an_operation_and_two_shapes() {
+    let s1 = Sphere::default();
+    let s2 = Cube::default();
+    let c = Csg::union(s1.clone(), s2.clone());
+
+    assert_eq!(c.operation, CsgOperation::Union);
+    assert_eq!(*c.left, s1.into());
+    assert_eq!(*c.right, s2.into());
+}
+
+#[test]
+fn evaluating_the_rule_for_csg_operations() {
+    use CsgOperation::*;
+    let cases = [
+        (Union, true, true, true, false),
+        (Union, true, true, false, true),
+        (Union, true, false, true, false),
+        (Union, true, false, false, true),
+        (Union, false, true, true, false),
+        (Union, false, true, false, false),
+        (Union, false, false, true, true),
+        (Union, false, false, false, true),
+        (Intersection, true, true, true, true),
+        (Intersection, true, true, false, false),
+        (Intersection, true, false, true, true),
+        (Intersection, true, false, false, false),
+        (Intersection, false, true, true, true),
+        (Intersection, false, true, false, true),
+        (Intersection, false, false, true, false),
+        (Intersection, false, false, false, false),
+        (Difference, true, true, true, false),
+        (Difference, true, true, false, true),
+        (Difference, true, false, true, false),
+        (Difference, true, false, false, true),
+        (Difference, false, true, true, true),
+        (Difference, false, true, false, true),
+        (Difference, false, false, true, false),
+        (Difference, false, false, false, false),
+    ];
+
+    for (operation, left_hit, in_left, in_right, allowed) in cases {
+        assert_eq!(
+            operation.allows(left_hit, in_left, in_right),
+            allowed,
+            "{:?} {} {} {}",
+            operation,
+            left_hit,
+            in_left,
+            in_right
+        );
+    }
+}
+
+#[test]
+fn filtering_a_list_of_intersections() {
+    let s1: Shape = Sphere::default().into();
+    let s2: Shape = Cube::default().into();
+    let hits = vec![
+        (Intersection::new(1.0, s1.clone()), true),
+        (Intersection::new(2.0, s2.clone()), false),
+        (Intersection::new(3.0, s1.clone()), true),
+        (Intersection::new(4.0, s2.clone()), false),
+    ];
+
+    for (operation, first, second) in [
+        (CsgOperation::Union, 0, 3),
+        (CsgOperation::Intersection, 1, 2),
+        (CsgOperation::Difference, 0, 1),
+    ] {
+        let c = Csg::new(operation, s1.clone(), s2.clone());
+        let result = c.filter_intersections(hits.clone());
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0], hits[first].0);
+        assert_eq!(result[1], hits[second].0);
+    }
+}
+
+#[test]
+fn a_ray_misses_a_csg_object() {
+    let c: Shape = Csg::union(Sphere::default(), Cube::default()).into();
+    let hits = c.intersect(Ray::new(point(0.0, 2.0, -5.0), vector(0.0, 0.0, 1.0)));
+
+    assert!(hits.is_empty());
+}
+
+#[test]
+fn a_ray_hits_a_csg_object() {
+    let s1 = Sphere::default();
+    let s2 = Sphere::default().with_transform(Matrix::translation(0.0, 0.0, 0.5));
+    let c: Shape = Csg::union(s1.clone(), s2.clone()).into();
+    let hits = c.intersect(Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0)));
+
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].t, 4.0);
+    assert_eq!(hits[0].object, s1.into());
+    assert_eq!(hits[1].t, 6.5);
+    assert_eq!(hits[1].object, s2.into());
+}
+
+#[test]
+fn difference_cuts_a_hole() {
+    // A unit cube with a thin sphere-shaped bite taken out of its front face
+    let bite = Sphere::default().with_transform(Matrix::translation(0.0, 0.0, -1.5));
+    let c: Shape = Csg::difference(Cube::default(), bite.clone()).into();
+    let hits = c.intersect(Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0)));
+
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].t, 4.5);
+    assert_eq!(hits[0].object, bite.clone().into());
+    // The bite's surface faces into the cube
+    assert_eq!(
+        hits[0].object.normal(point(0.0, 0.0, -0.5)),
+        vector(0.0, 0.0, 1.0)
+    );
+    assert_eq!(hits[1].t, 6.0);
+
+    let beside = c.intersect(Ray::new(point(0.9, 0.9, -5.0), vector(0.0, 0.0, 1.0)));
+    assert_eq!(beside[0].t, 4.0);
+}
+
+#[test]
+fn transformed_csg_moves_its_children() {
+    let c: Shape = Csg::intersection(
+        Sphere::default(),
+        Sphere::default().with_transform(Matrix::translation(0.0, 0.0, 1.0)),
+    )
+    .with_transform(Matrix::translation(5.0, 0.0, 0.0))
+    .into();
+    let hits = c.intersect(Ray::new(point(5.0, 0.0, -5.0), vector(0.0, 0.0, 1.0)));
+
+    // The lens between the two spheres runs from z = 0 to z = 1
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].t, 5.0);
+    assert_eq!(hits[1].t, 6.0);
+    assert_eq!(
+        hits[0].object.normal(point(5.0, 0.0, 0.0)),
+        vector(0.0, 0.0, -1.0)
+    );
+}
+
+#[test]
+fn csg_bounds_depend_on_the_operation() {
+    let left = Sphere::default();
+    let right = Sphere::default().with_transform(Matrix::translation(3.0, 0.0, 0.0));
+
+    let union = Csg::union(left.clone(), right.clone()).bounds();
+    assert_eq!(union.min, point(-1.0, -1.0, -1.0));
+    assert_eq!(union.max, point(4.0, 1.0, 1.0));
+
+    let difference = Csg::difference(left, right).bounds();
+    assert_eq!(difference.max, point(1.0, 1.0, 1.0));
+}
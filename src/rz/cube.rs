@@ -0,0 +1,178 @@
+#![allow(unused_imports)]
+use super::bounds::*;
+use super::intersection::*;
+use super::material::*;
+use super::matrix::*;
+use super::ray::*;
+use super::shape::*;
+use super::transform::*;
+use super::tuple::*;
+const EPSILON: f64 = 0.00001;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cube {
+    pub transform: Transform,
+    pub material: Material,
+}
+
+impl From<Cube> for Shape {
+    fn from(cube: Cube) -> Self {
+        Shape::Cube(cube)
+    }
+}
+
+impl Cube {
+    pub fn new(transform: Matrix<4>, material: Material) -> Self {
+        Self {
+            transform: transform.into(),
+            material,
+        }
+    }
+
+    pub fn default() -> Self {
+        Self {
+            transform: Transform::identity(),
+            material: Material::default_material(),
+        }
+    }
+
+    pub fn normal(&self, p: Tuple) -> Tuple {
+        let max = p.x.abs().max(p.y.abs()).max(p.z.abs());
+
+        if max == p.x.abs() {
+            vector(p.x, 0.0, 0.0)
+        } else if max == p.y.abs() {
+            vector(0.0, p.y, 0.0)
+        } else {
+            vector(0.0, 0.0, p.z)
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        Bounds::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0))
+    }
+
+    pub fn with_transform(&self, transform: impl Into<Transform>) -> Self {
+        Self {
+            transform: transform.into(),
+            material: self.material.clone(),
+        }
+    }
+
+    pub fn with_material(&self, material: Material) -> Self {
+        Self {
+            transform: self.transform,
+            material,
+        }
+    }
+
+    pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            return vec![];
+        }
+
+        vec![
+            Intersection::new(tmin, self.clone().into()),
+            Intersection::new(tmax, self.clone().into()),
+        ]
+    }
+}
+
+/// Where a ray enters and leaves the slab between -1 and 1 along one axis.
+fn check_axis(origin: f64, direction: f64) -> (f64, f64) {
+    let tmin_numerator = -1.0 - origin;
+    let tmax_numerator = 1.0 - origin;
+
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * f64::INFINITY,
+            tmax_numerator * f64::INFINITY,
+        )
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+#[test]
+fn ray_intersects_a_cube() {
+    let obj: Shape = Cube::default().into();
+    let cases = [
+        (point(5.0, 0.5, 0.0), vector(-1.0, 0.0, 0.0), 4.0, 6.0),
+        (point(-5.0, 0.5, 0.0), vector(1.0, 0.0, 0.0), 4.0, 6.0),
+        (point(0.5, 5.0, 0.0), vector(0.0, -1.0, 0.0), 4.0, 6.0),
+        (point(0.5, -5.0, 0.0), vector(0.0, 1.0, 0.0), 4.0, 6.0),
+        (point(0.5, 0.0, 5.0), vector(0.0, 0.0, -1.0), 4.0, 6.0),
+        (point(0.5, 0.0, -5.0), vector(0.0, 0.0, 1.0), 4.0, 6.0),
+        (point(0.0, 0.5, 0.0), vector(0.0, 0.0, 1.0), -1.0, 1.0),
+    ];
+
+    for (origin, direction, t1, t2) in cases {
+        let hits = obj.intersect(Ray::new(origin, direction));
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].t, t1);
+        assert_eq!(hits[1].t, t2);
+    }
+}
+
+#[test]
+fn ray_misses_a_cube() {
+    let obj: Shape = Cube::default().into();
+    let cases = [
+        (point(-2.0, 0.0, 0.0), vector(0.2673, 0.5345, 0.8018)),
+        (point(0.0, -2.0, 0.0), vector(0.8018, 0.2673, 0.5345)),
+        (point(0.0, 0.0, -2.0), vector(0.5345, 0.8018, 0.2673)),
+        (point(2.0, 0.0, 2.0), vector(0.0, 0.0, -1.0)),
+        (point(0.0, 2.0, 2.0), vector(0.0, -1.0, 0.0)),
+        (point(2.0, 2.0, 0.0), vector(-1.0, 0.0, 0.0)),
+    ];
+
+    for (origin, direction) in cases {
+        let hits = obj.intersect(Ray::new(origin, direction));
+        assert!(hits.is_empty());
+    }
+}
+
+#[test]
+fn normal_on_surface_of_cube() {
+    let obj: Shape = Cube::default().into();
+    let cases = [
+        (point(1.0, 0.5, -0.8), vector(1.0, 0.0, 0.0)),
+        (point(-1.0, -0.2, 0.9), vector(-1.0, 0.0, 0.0)),
+        (point(-0.4, 1.0, -0.1), vector(0.0, 1.0, 0.0)),
+        (point(0.3, -1.0, -0.7), vector(0.0, -1.0, 0.0)),
+        (point(-0.6, 0.3, 1.0), vector(0.0, 0.0, 1.0)),
+        (point(0.4, 0.4, -1.0), vector(0.0, 0.0, -1.0)),
+        (point(1.0, 1.0, 1.0), vector(1.0, 0.0, 0.0)),
+        (point(-1.0, -1.0, -1.0), vector(-1.0, 0.0, 0.0)),
+    ];
+
+    for (p, expected) in cases {
+        assert_eq!(obj.normal(p), expected);
+    }
+}
+
+#[test]
+fn intersecting_a_transformed_cube() {
+    let obj: Shape = Cube::default()
+        .with_transform(Matrix::translation(0.0, 0.0, 5.0))
+        .into();
+    let hits = obj.intersect(Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0)));
+
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].t, 4.0);
+    assert_eq!(hits[1].t, 6.0);
+}
@@ -0,0 +1,254 @@
+#![allow(unused_imports)]
+use super::bounds::*;
+use super::intersection::*;
+use super::material::*;
+use super::matrix::*;
+use super::ray::*;
+use super::shape::*;
+use super::transform::*;
+use super::tuple::*;
+const EPSILON: f64 = 0.00001;
+
+/// A cylinder of radius 1 around the y axis, optionally truncated to lie between
+/// `minimum` and `maximum` (exclusive) and capped at both ends when `closed`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cylinder {
+    pub transform: Transform,
+    pub material: Material,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+
+impl From<Cylinder> for Shape {
+    fn from(cylinder: Cylinder) -> Self {
+        Shape::Cylinder(cylinder)
+    }
+}
+
+impl Cylinder {
+    pub fn new(transform: Matrix<4>, material: Material) -> Self {
+        Self {
+            transform: transform.into(),
+            material,
+            ..Self::default()
+        }
+    }
+
+    pub fn default() -> Self {
+        Self {
+            transform: Transform::identity(),
+            material: Material::default_material(),
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+
+    pub fn normal(&self, p: Tuple) -> Tuple {
+        let dist = p.x * p.x + p.z * p.z;
+
+        if dist < 1.0 && p.y >= self.maximum - EPSILON {
+            vector(0.0, 1.0, 0.0)
+        } else if dist < 1.0 && p.y <= self.minimum + EPSILON {
+            vector(0.0, -1.0, 0.0)
+        } else {
+            vector(p.x, 0.0, p.z)
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        Bounds::new(
+            point(-1.0, self.minimum, -1.0),
+            point(1.0, self.maximum, 1.0),
+        )
+    }
+
+    pub fn with_transform(&self, transform: impl Into<Transform>) -> Self {
+        Self {
+            transform: transform.into(),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_material(&self, material: Material) -> Self {
+        Self {
+            material,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_bounds(&self, minimum: f64, maximum: f64, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+            ..self.clone()
+        }
+    }
+
+    pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+        let mut hits = vec![];
+
+        let a = ray.direction.x * ray.direction.x + ray.direction.z * ray.direction.z;
+
+        // A ray parallel to the y axis can only hit the caps
+        if a.abs() >= EPSILON {
+            let b = 2.0 * ray.origin.x * ray.direction.x + 2.0 * ray.origin.z * ray.direction.z;
+            let c = ray.origin.x * ray.origin.x + ray.origin.z * ray.origin.z - 1.0;
+            let det = b * b - 4.0 * a * c;
+
+            if det < 0.0 {
+                return vec![];
+            }
+
+            let t0 = (-b - det.sqrt()) / (2.0 * a);
+            let t1 = (-b + det.sqrt()) / (2.0 * a);
+
+            for t in [t0.min(t1), t0.max(t1)] {
+                let y = ray.origin.y + t * ray.direction.y;
+                if self.minimum < y && y < self.maximum {
+                    hits.push(Intersection::new(t, self.clone().into()));
+                }
+            }
+        }
+
+        self.intersect_caps(ray, &mut hits);
+        hits
+    }
+
+    fn intersect_caps(&self, ray: Ray, hits: &mut Vec<Intersection>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, 1.0) {
+                hits.push(Intersection::new(t, self.clone().into()));
+            }
+        }
+    }
+}
+
+/// Whether the ray at `t` is within `radius` of the y axis.
+pub(crate) fn check_cap(ray: Ray, t: f64, radius: f64) -> bool {
+    let x = ray.origin.x + t * ray.direction.x;
+    let z = ray.origin.z + t * ray.direction.z;
+
+    x * x + z * z <= radius * radius
+}
+
+#[test]
+fn ray_misses_a_cylinder() {
+    let obj: Shape = Cylinder::default().into();
+    let cases = [
+        (point(1.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)),
+        (point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)),
+        (point(0.0, 0.0, -5.0), vector(1.0, 1.0, 1.0)),
+    ];
+
+    for (origin, direction) in cases {
+        let hits = obj.intersect(Ray::new(origin, direction));
+        assert!(hits.is_empty());
+    }
+}
+
+#[test]
+fn ray_strikes_a_cylinder() {
+    let obj: Shape = Cylinder::default().into();
+    let cases = [
+        (point(1.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 5.0, 5.0),
+        (point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 4.0, 6.0),
+        (
+            point(0.5, 0.0, -5.0),
+            vector(0.1, 1.0, 1.0),
+            6.80798,
+            7.08872,
+        ),
+    ];
+
+    for (origin, direction, t0, t1) in cases {
+        let hits = obj.intersect(Ray::new(origin, direction));
+
+        assert_eq!(hits.len(), 2);
+        assert!((hits[0].t - t0).abs() < EPSILON);
+        assert!((hits[1].t - t1).abs() < EPSILON);
+    }
+}
+
+#[test]
+fn normal_on_a_cylinder() {
+    let cyl = Cylinder::default();
+    let cases = [
+        (point(1.0, 0.0, 0.0), vector(1.0, 0.0, 0.0)),
+        (point(0.0, 5.0, -1.0), vector(0.0, 0.0, -1.0)),
+        (point(0.0, -2.0, 1.0), vector(0.0, 0.0, 1.0)),
+        (point(-1.0, 1.0, 0.0), vector(-1.0, 0.0, 0.0)),
+    ];
+
+    for (p, expected) in cases {
+        assert_eq!(cyl.normal(p), expected);
+    }
+}
+
+#[test]
+fn default_cylinder_is_infinite_and_open() {
+    let cyl = Cylinder::default();
+
+    assert_eq!(cyl.minimum, f64::NEG_INFINITY);
+    assert_eq!(cyl.maximum, f64::INFINITY);
+    assert!(!cyl.closed);
+}
+
+#[test]
+fn intersecting_a_truncated_cylinder() {
+    let obj: Shape = Cylinder::default().with_bounds(1.0, 2.0, false).into();
+    let cases = [
+        (point(0.0, 1.5, 0.0), vector(0.1, 1.0, 0.0), 0),
+        (point(0.0, 3.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+        (point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+        (point(0.0, 2.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+        (point(0.0, 1.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+        (point(0.0, 1.5, -2.0), vector(0.0, 0.0, 1.0), 2),
+    ];
+
+    for (origin, direction, count) in cases {
+        let hits = obj.intersect(Ray::new(origin, direction));
+        assert_eq!(hits.len(), count);
+    }
+}
+
+#[test]
+fn intersecting_the_caps_of_a_closed_cylinder() {
+    let obj: Shape = Cylinder::default().with_bounds(1.0, 2.0, true).into();
+    let cases = [
+        (point(0.0, 3.0, 0.0), vector(0.0, -1.0, 0.0), 2),
+        (point(0.0, 3.0, -2.0), vector(0.0, -1.0, 2.0), 2),
+        (point(0.0, 4.0, -2.0), vector(0.0, -1.0, 1.0), 2),
+        (point(0.0, 0.0, -2.0), vector(0.0, 1.0, 2.0), 2),
+        (point(0.0, -1.0, -2.0), vector(0.0, 1.0, 1.0), 2),
+    ];
+
+    for (origin, direction, count) in cases {
+        let hits = obj.intersect(Ray::new(origin, direction));
+        assert_eq!(hits.len(), count);
+    }
+}
+
+#[test]
+fn normal_on_cylinder_end_caps() {
+    let cyl = Cylinder::default().with_bounds(1.0, 2.0, true);
+    let cases = [
+        (point(0.0, 1.0, 0.0), vector(0.0, -1.0, 0.0)),
+        (point(0.5, 1.0, 0.0), vector(0.0, -1.0, 0.0)),
+        (point(0.0, 1.0, 0.5), vector(0.0, -1.0, 0.0)),
+        (point(0.0, 2.0, 0.0), vector(0.0, 1.0, 0.0)),
+        (point(0.5, 2.0, 0.0), vector(0.0, 1.0, 0.0)),
+        (point(0.0, 2.0, 0.5), vector(0.0, 1.0, 0.0)),
+    ];
+
+    for (p, expected) in cases {
+        assert_eq!(cyl.normal(p), expected);
+    }
+}
@@ -0,0 +1,280 @@
+#![allow(unused_imports)]
+use super::camera::*;
+use super::canvas::*;
+use super::color::*;
+use super::integrator::*;
+use super::matrix::*;
+use super::sampling::*;
+use super::scene::*;
+use super::tonemap::*;
+use super::tuple::*;
+use super::world::*;
+use std::env;
+use std::f64::consts::PI;
+use std::fs;
+use std::path::PathBuf;
+
+/// Set to regenerate the golden images instead of comparing against them.
+pub const UPDATE_GOLDEN_VAR: &str = "RZ_UPDATE_GOLDEN";
+
+/// How far a render may stray from its golden image.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tolerance {
+    /// The largest difference allowed in any channel of any one pixel.
+    pub per_pixel: f64,
+    /// The largest root mean square difference allowed over the whole image.
+    pub rmse: f64,
+}
+
+impl Default for Tolerance {
+    /// Loose enough for 8-bit rounding and tiny floating point differences,
+    /// tight enough to catch any visible change.
+    fn default() -> Self {
+        Tolerance {
+            per_pixel: 2.0 / 255.0,
+            rmse: 0.5 / 255.0,
+        }
+    }
+}
+
+/// How two images of the same size differ.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageDiff {
+    /// Root mean square difference over every channel of every pixel.
+    pub rmse: f64,
+    /// The largest difference in any channel.
+    pub max_difference: f64,
+    /// The number of pixels with some channel beyond the per-pixel tolerance.
+    pub pixels_over: usize,
+    /// Each pixel's largest channel difference, from black (none) through red
+    /// and yellow to white (the largest difference in the image).
+    pub heat_map: Canvas,
+}
+
+impl ImageDiff {
+    /// Compares two images after clamping them to the displayable range, the
+    /// way they would be saved. Returns `None` if their sizes differ.
+    pub fn new(actual: &Canvas, expected: &Canvas, per_pixel: f64) -> Option<ImageDiff> {
+        if (actual.width, actual.height) != (expected.width, expected.height) {
+            return None;
+        }
+
+        let clamp = ToneMapping::default();
+        let (width, height) = (actual.width, actual.height);
+        let mut differences = Vec::with_capacity(width * height);
+        let mut sum_of_squares = 0.0;
+        let mut pixels_over = 0;
+
+        for y in 0..height {
+            for x in 0..width {
+                let a = clamp.apply(actual.pixel_at(x, y));
+                let e = clamp.apply(expected.pixel_at(x, y));
+                let channels = [a.r - e.r, a.g - e.g, a.b - e.b].map(f64::abs);
+
+                sum_of_squares += channels.iter().map(|d| d * d).sum::<f64>();
+                let largest = channels.into_iter().fold(0.0, f64::max);
+                if largest > per_pixel {
+                    pixels_over += 1;
+                }
+                differences.push(largest);
+            }
+        }
+
+        let max_difference = differences.iter().copied().fold(0.0, f64::max);
+        let mut heat_map = Canvas::new(width, height);
+        for (i, difference) in differences.into_iter().enumerate() {
+            let heat = if max_difference > 0.0 {
+                difference / max_difference
+            } else {
+                0.0
+            };
+            heat_map.write(i % width, i / width, heat_color(heat));
+        }
+
+        Some(ImageDiff {
+            rmse: (sum_of_squares / (3 * width * height).max(1) as f64).sqrt(),
+            max_difference,
+            pixels_over,
+            heat_map,
+        })
+    }
+
+    pub fn within(&self, tolerance: &Tolerance) -> bool {
+        self.pixels_over == 0 && self.rmse <= tolerance.rmse
+    }
+}
+
+/// Black at 0, through red and yellow, to white at 1.
+fn heat_color(heat: f64) -> Color {
+    let channel = |start: f64| ((heat - start) * 3.0).clamp(0.0, 1.0);
+    Color::new(channel(0.0), channel(1.0 / 3.0), channel(2.0 / 3.0))
+}
+
+/// Compares a render with the golden image called `name`.
+///
+/// Golden images live in `tests/golden` as plain PPM files. When the render
+/// doesn't match, the render and a heat map of the differences are written to
+/// `target/golden` for inspection. Setting `RZ_UPDATE_GOLDEN` overwrites the
+/// golden image with the render instead.
+pub fn check_golden(name: &str, actual: &Canvas, tolerance: &Tolerance) -> Result<(), String> {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let golden = root.join("tests/golden").join(format!("{}.ppm", name));
+
+    if env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        fs::create_dir_all(golden.parent().unwrap()).map_err(|err| err.to_string())?;
+        fs::write(&golden, actual.to_ppm(&ToneMapping::default()))
+            .map_err(|err| format!("{}: {}", golden.display(), err))?;
+        return Ok(());
+    }
+
+    let expected = Canvas::load(&golden.to_string_lossy()).map_err(|err| {
+        format!(
+            "{}: {} (set {} to create it)",
+            golden.display(),
+            err,
+            UPDATE_GOLDEN_VAR
+        )
+    })?;
+
+    let failure = match ImageDiff::new(actual, &expected, tolerance.per_pixel) {
+        None => format!(
+            "render is {}x{} but the golden image is {}x{}",
+            actual.width, actual.height, expected.width, expected.height
+        ),
+        Some(diff) if diff.within(tolerance) => return Ok(()),
+        Some(diff) => {
+            let out = root.join("target/golden");
+            fs::create_dir_all(&out).map_err(|err| err.to_string())?;
+            let heat_map = out.join(format!("{}-diff.png", name));
+            diff.heat_map
+                .save(&heat_map.to_string_lossy())
+                .map_err(|err| err.to_string())?;
+
+            format!(
+                "{} pixels differ by more than {:.4}, largest difference {:.4}, RMSE {:.5} (allowed {:.5}); heat map in {}",
+                diff.pixels_over,
+                tolerance.per_pixel,
+                diff.max_difference,
+                diff.rmse,
+                tolerance.rmse,
+                heat_map.display()
+            )
+        }
+    };
+
+    let out = root.join("target/golden");
+    fs::create_dir_all(&out).map_err(|err| err.to_string())?;
+    let render = out.join(format!("{}.png", name));
+    actual
+        .save(&render.to_string_lossy())
+        .map_err(|err| err.to_string())?;
+
+    Err(format!(
+        "{} doesn't match {}: {}; render in {}",
+        name,
+        golden.display(),
+        failure,
+        render.display()
+    ))
+}
+
+#[cfg(test)]
+fn assert_golden(name: &str, actual: &Canvas) {
+    if let Err(message) = check_golden(name, actual, &Tolerance::default()) {
+        panic!("{}", message);
+    }
+}
+
+#[cfg(test)]
+fn render_scene_file(file: &str, width: usize, height: usize) -> Canvas {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(file);
+    let scene = Scene::load(&path.to_string_lossy()).unwrap();
+    scene.camera.with_size(width, height).render(&scene.world)
+}
+
+#[test]
+fn identical_images_have_no_difference() {
+    let mut image = Canvas::new(2, 2);
+    image.write(1, 0, color(0.2, 0.4, 0.6));
+    let diff = ImageDiff::new(&image, &image, 0.0).unwrap();
+
+    assert_eq!(diff.rmse, 0.0);
+    assert_eq!(diff.pixels_over, 0);
+    assert_eq!(diff.heat_map, Canvas::new(2, 2));
+    assert!(diff.within(&Tolerance::default()));
+}
+
+#[test]
+fn differences_are_measured_and_mapped() {
+    let expected = Canvas::new(2, 2);
+    let mut actual = Canvas::new(2, 2);
+    actual.write(0, 0, color(0.5, 0.0, 0.0));
+    actual.write(1, 1, color(0.25, 0.0, 0.0));
+    let diff = ImageDiff::new(&actual, &expected, 0.3).unwrap();
+
+    assert_eq!(diff.max_difference, 0.5);
+    assert_eq!(diff.pixels_over, 1);
+    assert!((diff.rmse - (0.3125_f64 / 12.0).sqrt()).abs() < 1e-12);
+    assert_eq!(diff.heat_map.pixel_at(0, 0), Color::white());
+    assert_eq!(diff.heat_map.pixel_at(1, 1), color(1.0, 0.5, 0.0));
+    assert_eq!(diff.heat_map.pixel_at(1, 0), Color::black());
+    assert!(!diff.within(&Tolerance::default()));
+}
+
+#[test]
+fn over_bright_pixels_compare_as_saved() {
+    let mut expected = Canvas::new(1, 1);
+    expected.write(0, 0, Color::white());
+    let mut actual = Canvas::new(1, 1);
+    actual.write(0, 0, color(3.0, 1.5, 1.0));
+
+    assert_eq!(ImageDiff::new(&actual, &expected, 0.0).unwrap().rmse, 0.0);
+}
+
+#[test]
+fn images_of_different_sizes_dont_compare() {
+    assert_eq!(
+        ImageDiff::new(&Canvas::new(2, 2), &Canvas::new(2, 3), 0.0),
+        None
+    );
+}
+
+#[test]
+fn golden_default_world() {
+    let mut camera = Camera::new(32, 32, PI / 2.0);
+    camera.set_transform(Matrix::view(
+        point(0.0, 0.0, -5.0),
+        point(0.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+
+    assert_golden("default_world", &camera.render(&World::default()));
+}
+
+#[test]
+fn golden_spheres_scene() {
+    assert_golden("spheres", &render_scene_file("scenes/spheres.yml", 64, 32));
+}
+
+#[test]
+fn golden_shapes_scene() {
+    assert_golden("shapes", &render_scene_file("scenes/shapes.yml", 64, 32));
+}
+
+#[test]
+fn golden_csg_scene() {
+    assert_golden("csg", &render_scene_file("scenes/csg.yml", 40, 30));
+}
+
+#[test]
+fn golden_cornell_box_path_traced() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("scenes/cornell.yml");
+    let scene = Scene::load(&path.to_string_lossy()).unwrap();
+    let camera = scene
+        .camera
+        .with_size(32, 32)
+        .with_sampling(Sampling::Jittered(4))
+        .with_integrator(Integrator::PathTraced);
+
+    assert_golden("cornell_path_traced", &camera.render(&scene.world));
+}
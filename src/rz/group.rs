@@ -0,0 +1,275 @@
+#![allow(unused_imports)]
+use super::bounds::*;
+use super::bvh::*;
+use super::cylinder::*;
+use super::intersection::*;
+use super::material::*;
+use super::matrix::*;
+use super::ray::*;
+use super::shape::*;
+use super::sphere::*;
+use super::transform::*;
+use super::tuple::*;
+use std::f64::consts::PI;
+
+/// A collection of shapes that share a transform, so they can be placed in the
+/// world as a single unit. Each child's own transform is relative to the group.
+///
+/// Large groups such as meshes should call `build_bvh` once they are complete.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub transform: Transform,
+    children: Vec<Shape>,
+    bvh: Option<Bvh>,
+}
+
+impl From<Group> for Shape {
+    fn from(group: Group) -> Self {
+        Shape::Group(group)
+    }
+}
+
+impl Group {
+    pub fn new(transform: Matrix<4>, children: Vec<Shape>) -> Self {
+        Self {
+            transform: transform.into(),
+            children,
+            bvh: None,
+        }
+    }
+
+    pub fn default() -> Self {
+        Self {
+            transform: Transform::identity(),
+            children: vec![],
+            bvh: None,
+        }
+    }
+
+    pub fn with_transform(self, transform: impl Into<Transform>) -> Self {
+        Self {
+            transform: transform.into(),
+            ..self
+        }
+    }
+
+    pub fn with_child(mut self, child: impl Into<Shape>) -> Self {
+        self.add_child(child);
+        self
+    }
+
+    pub fn children(&self) -> &[Shape] {
+        &self.children
+    }
+
+    /// The same group with `material` handed down to every child.
+    pub fn with_material(&self, material: Material) -> Self {
+        Self {
+            transform: self.transform,
+            children: self
+                .children
+                .iter()
+                .map(|child| child.with_material(material.clone()))
+                .collect(),
+            bvh: None,
+        }
+    }
+
+    pub fn add_child(&mut self, child: impl Into<Shape>) {
+        self.children.push(child.into());
+        self.bvh = None;
+    }
+
+    /// Builds bounding volume hierarchies for this group and any groups inside it.
+    pub fn build_bvh(&mut self) {
+        for child in self.children.iter_mut() {
+            match child {
+                Shape::Group(group) => group.build_bvh(),
+                Shape::Csg(csg) => csg.build_bvh(),
+                _ => {}
+            }
+        }
+
+        self.bvh = Some(Bvh::build(&self.children));
+    }
+
+    /// The box around all of the children, in the group's space.
+    pub fn bounds(&self) -> Bounds {
+        self.children
+            .iter()
+            .fold(Bounds::empty(), |acc, child| acc.merged(&child.bounds()))
+    }
+
+    pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+        let mut hits: Vec<Intersection> = match &self.bvh {
+            Some(bvh) => bvh.intersect(&self.children, ray),
+            None => self
+                .children
+                .iter()
+                .flat_map(|child| child.intersect(ray))
+                .collect(),
+        };
+
+        hits.sort_unstable_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+        hits
+    }
+}
+
+#[test]
+fn creating_a_new_group() {
+    let g = Group::default();
+
+    assert_eq!(g.transform, Transform::identity());
+    assert!(g.children.is_empty());
+}
+
+#[test]
+fn adding_a_child_to_a_group() {
+    let s = Sphere::default();
+    let g = Group::default().with_child(s.clone());
+
+    assert_eq!(g.children, vec![s.into()]);
+}
+
+#[test]
+fn intersecting_a_ray_with_an_empty_group() {
+    let g: Shape = Group::default().into();
+    let hits = g.intersect(Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0)));
+
+    assert!(hits.is_empty());
+}
+
+#[test]
+fn intersecting_a_ray_with_a_nonempty_group() {
+    let s1 = Sphere::default();
+    let s2 = Sphere::default().with_transform(Matrix::translation(0.0, 0.0, -3.0));
+    let s3 = Sphere::default().with_transform(Matrix::translation(5.0, 0.0, 0.0));
+    let g: Shape = Group::default()
+        .with_child(s1.clone())
+        .with_child(s2.clone())
+        .with_child(s3)
+        .into();
+    let hits = g.intersect(Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0)));
+
+    assert_eq!(hits.len(), 4);
+    assert_eq!(hits[0].object, s2.clone().into());
+    assert_eq!(hits[1].object, s2.into());
+    assert_eq!(hits[2].object, s1.clone().into());
+    assert_eq!(hits[3].object, s1.into());
+}
+
+#[test]
+fn intersecting_a_transformed_group() {
+    let s = Sphere::default().with_transform(Matrix::translation(5.0, 0.0, 0.0));
+    let g: Shape = Group::default()
+        .with_transform(Matrix::scaling(2.0, 2.0, 2.0))
+        .with_child(s)
+        .into();
+    let hits = g.intersect(Ray::new(point(10.0, 0.0, -10.0), vector(0.0, 0.0, 1.0)));
+
+    assert_eq!(hits.len(), 2);
+}
+
+#[test]
+fn converting_a_point_from_world_to_object_space() {
+    let s = Sphere::default().with_transform(Matrix::translation(5.0, 0.0, 0.0));
+    let g2 = Group::default()
+        .with_transform(Matrix::scaling(2.0, 2.0, 2.0))
+        .with_child(s);
+    let g1: Shape = Group::default()
+        .with_transform(Matrix::rotation_y(PI / 2.0))
+        .with_child(g2)
+        .into();
+
+    let hits = g1.intersect(Ray::new(point(-20.0, 0.0, -10.0), vector(1.0, 0.0, 0.0)));
+    assert_eq!(hits.len(), 2);
+
+    let world_to_object = hits[0].object.transform().inverse();
+    assert_eq!(
+        world_to_object * point(-2.0, 0.0, -10.0),
+        point(0.0, 0.0, -1.0)
+    );
+}
+
+#[test]
+fn finding_the_normal_on_a_child_object() {
+    let s = Sphere::default().with_transform(Matrix::translation(5.0, 0.0, 0.0));
+    let g2 = Group::default()
+        .with_transform(Matrix::scaling(1.0, 2.0, 3.0))
+        .with_child(s.clone());
+    let g1 = Group::default()
+        .with_transform(Matrix::rotation_y(PI / 2.0))
+        .with_child(g2);
+
+    // The child as the world sees it, with both groups' transforms applied
+    let child = Shape::from(s)
+        .with_parent(g1.children[0].transform())
+        .with_parent(&g1.transform);
+    let n = child.normal(point(1.7321, 1.1547, -5.5774));
+
+    assert_eq!(n, vector(0.285704, 0.428543, -0.857161));
+}
+
+#[test]
+fn moving_a_group_moves_its_children() {
+    let top = Sphere::default().with_transform(Matrix::scaling(2.0, 0.1, 2.0));
+    let table: Shape = Group::default()
+        .with_child(top)
+        .with_transform(Matrix::translation(10.0, 0.0, 0.0))
+        .into();
+
+    let hits = table.intersect(Ray::new(point(10.0, 5.0, 0.0), vector(0.0, -1.0, 0.0)));
+    assert_eq!(hits.len(), 2);
+    assert!((hits[0].t - 4.9).abs() < 0.00001);
+    assert_eq!(
+        hits[0].object.normal(point(10.0, 0.1, 0.0)),
+        vector(0.0, 1.0, 0.0)
+    );
+
+    let miss = table.intersect(Ray::new(point(0.0, 5.0, 0.0), vector(0.0, -1.0, 0.0)));
+    assert!(miss.is_empty());
+}
+
+#[test]
+fn group_bounds_contain_transformed_children() {
+    let s = Sphere::default().with_transform(
+        Matrix::identity()
+            .scale(2.0, 2.0, 2.0)
+            .translate(2.0, 5.0, -3.0),
+    );
+    let c = Cylinder::default()
+        .with_bounds(-2.0, 2.0, false)
+        .with_transform(
+            Matrix::identity()
+                .scale(0.5, 1.0, 0.5)
+                .translate(-4.0, -1.0, 4.0),
+        );
+    let g = Group::default().with_child(s).with_child(c);
+    let b = g.bounds();
+
+    assert_eq!(b.min, point(-4.5, -3.0, -5.0));
+    assert_eq!(b.max, point(4.0, 7.0, 4.5));
+}
+
+#[test]
+fn group_with_bvh_intersects_like_one_without() {
+    let mut g = Group::default();
+    for i in 0..20 {
+        g.add_child(Sphere::default().with_transform(Matrix::translation(
+            i as f64 * 3.0 - 30.0,
+            0.0,
+            0.0,
+        )));
+    }
+    let brute_force: Shape = g.clone().into();
+    g.build_bvh();
+    let with_bvh: Shape = g.into();
+
+    for i in 0..20 {
+        let ray = Ray::new(
+            point(i as f64 * 3.3 - 31.0, 0.5, -5.0),
+            vector(0.0, 0.0, 1.0),
+        );
+        assert_eq!(with_bvh.intersect(ray), brute_force.intersect(ray));
+    }
+}
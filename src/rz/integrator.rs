@@ -0,0 +1,312 @@
+#![allow(unused_imports)]
+use super::color::*;
+use super::intersection::*;
+use super::lights::*;
+use super::material::*;
+use super::matrix::*;
+use super::plane::*;
+use super::ray::*;
+use super::rng::*;
+use super::shape::*;
+use super::sphere::*;
+use super::tuple::*;
+use super::world::*;
+use std::f64::consts::PI;
+
+/// Bounces after which Russian roulette may end a path.
+const MIN_BOUNCES: usize = 3;
+
+/// Hard limit on path length, in case a path keeps surviving the roulette.
+const MAX_BOUNCES: usize = 64;
+
+/// How the color seen along a camera ray is worked out.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Integrator {
+    /// Direct lighting with Phong shading, plus mirror reflection and
+    /// refraction up to the world's `max_depth`.
+    #[default]
+    Whitted,
+    /// Monte Carlo path tracing: light bounces off diffuse surfaces too, so
+    /// objects light each other and emissive surfaces act as lights. Paths end
+    /// by Russian roulette or after `MAX_BOUNCES`; the world's `max_depth`
+    /// doesn't apply.
+    PathTraced,
+}
+
+impl Integrator {
+    pub fn color(&self, world: &World, ray: &Ray, rng: &mut Rng) -> Color {
+        match self {
+            Integrator::Whitted => world.color(ray),
+            Integrator::PathTraced => world.path_trace(ray, rng),
+        }
+    }
+}
+
+impl World {
+    /// One random light path's estimate of the color seen along `ray`.
+    ///
+    /// At each hit the path picks one way to continue: a mirror reflection, a
+    /// refraction, or a diffuse bounce in a cosine-weighted direction, in
+    /// proportion to how much each contributes. Light sources are sampled
+    /// directly at every hit, as in Whitted shading but without the ambient
+    /// term, which the diffuse bounces replace. Averaging many paths per pixel
+    /// converges on the full global illumination.
+    pub fn path_trace(&self, ray: &Ray, rng: &mut Rng) -> Color {
+        let mut radiance = Color::black();
+        let mut throughput = Color::white();
+        let mut ray = *ray;
+
+        for bounce in 0..MAX_BOUNCES {
+            let hits = self.intersect(ray);
+            let Some(hit) = hit(&hits) else {
+                break;
+            };
+            let comps = IntersectionInfo::prepare_computations(&hit, ray, &hits);
+            let material = comps.object.material();
+
+            radiance = radiance + throughput * (material.emissive + self.direct_light(&comps));
+
+            let albedo = material.color_at(&comps.object, comps.point) * material.diffuse;
+            let (reflect, refract) = if material.reflective > 0.0 && material.transparency > 0.0 {
+                let reflectance = comps.schlick();
+                (
+                    material.reflective * reflectance,
+                    material.transparency * (1.0 - reflectance),
+                )
+            } else {
+                (material.reflective, material.transparency)
+            };
+            let diffuse = max_component(albedo);
+
+            let total = reflect + refract + diffuse;
+            if total <= 0.0 {
+                break;
+            }
+
+            // Pick one way to continue, weighting the path by the total so that
+            // on average every way is counted as much as it contributes.
+            let choice = rng.next_f64() * total;
+            ray = if choice < reflect {
+                throughput = throughput * total;
+                Ray::new(comps.over_point, comps.reflect)
+            } else if choice < reflect + refract {
+                throughput = throughput * total;
+                match comps.refracted_direction() {
+                    Some(direction) => Ray::new(comps.under_point, direction),
+                    // Total internal reflection: the light reflects instead
+                    None => Ray::new(comps.over_point, comps.reflect),
+                }
+            } else {
+                throughput = throughput * albedo * (total / diffuse);
+                Ray::new(comps.over_point, cosine_hemisphere(comps.normal, rng))
+            };
+
+            // Russian roulette: end dim paths early, boosting the survivors to
+            // make up for the ones that were cut.
+            if bounce + 1 >= MIN_BOUNCES {
+                let survival = max_component(throughput).min(1.0);
+                if rng.next_f64() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+
+        radiance
+    }
+
+    /// Light reaching a hit straight from the light sources, shaded as by
+    /// `shade_hit` but without ambient light.
+    fn direct_light(&self, comps: &IntersectionInfo) -> Color {
+        let material = Material {
+            ambient: 0.0,
+            ..comps.object.material()
+        };
+
+        self.lights
+            .iter()
+            .map(|light| {
+                let intensity = self.intensity_at(light, &comps.over_point);
+                material.lighting(
+                    &comps.object,
+                    light,
+                    comps.point,
+                    comps.eye,
+                    comps.normal,
+                    intensity,
+                )
+            })
+            .fold(Color::black(), |acc, c| acc + c)
+    }
+}
+
+/// A random direction in the hemisphere around `normal`, more likely the
+/// closer it is to the normal: the density is proportional to the cosine of
+/// the angle between them, which cancels the cosine in diffuse reflection.
+pub fn cosine_hemisphere(normal: Tuple, rng: &mut Rng) -> Tuple {
+    let r = rng.next_f64().sqrt();
+    let phi = 2.0 * PI * rng.next_f64();
+    let (x, y) = (r * phi.cos(), r * phi.sin());
+    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+
+    // Any two unit vectors perpendicular to the normal and to each other
+    let helper = if normal.x.abs() > 0.9 {
+        vector(0.0, 1.0, 0.0)
+    } else {
+        vector(1.0, 0.0, 0.0)
+    };
+    let tangent = helper.cross(normal).normalized();
+    let bitangent = normal.cross(tangent);
+
+    (tangent * x + bitangent * y + normal * z).normalized()
+}
+
+fn max_component(c: Color) -> f64 {
+    c.r.max(c.g).max(c.b)
+}
+
+#[cfg(test)]
+fn average_path(world: &World, ray: Ray, paths: usize) -> Color {
+    let mut rng = Rng::new(1);
+    let sum = (0..paths).fold(Color::black(), |sum, _| {
+        sum + world.path_trace(&ray, &mut rng)
+    });
+
+    sum / paths as f64
+}
+
+#[test]
+fn cosine_samples_favor_the_normal() {
+    let normal = vector(1.0, 2.0, -2.0).normalized();
+    let mut rng = Rng::new(3);
+    let n = 20000;
+    let mut sum = 0.0;
+
+    for _ in 0..n {
+        let d = cosine_hemisphere(normal, &mut rng);
+        assert!((d.magnitude() - 1.0).abs() < 1e-9);
+        assert!(d.dot(normal) >= 0.0);
+        sum += d.dot(normal);
+    }
+
+    // The mean cosine under a cosine-weighted density is 2/3
+    assert!((sum / n as f64 - 2.0 / 3.0).abs() < 0.01);
+}
+
+#[test]
+fn whitted_integrator_matches_world_color() {
+    let world = World::default();
+    let ray = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+
+    assert_eq!(
+        Integrator::Whitted.color(&world, &ray, &mut Rng::new(0)),
+        world.color(&ray)
+    );
+}
+
+#[test]
+fn path_tracing_a_lone_diffuse_object_is_direct_lighting() {
+    // Rays bouncing off a lone sphere never come back to it, so only the
+    // direct light is left, whichever way the paths go.
+    let mut world = World::new();
+    world.add_object(Sphere::default());
+    let ray = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+
+    let mut material = Material::default_material();
+    material.ambient = 0.0;
+    let mut unlit = World::new();
+    unlit.add_object(Sphere::default().with_material(material));
+
+    assert_eq!(world.path_trace(&ray, &mut Rng::new(9)), unlit.color(&ray));
+}
+
+#[test]
+fn path_tracing_misses_are_black() {
+    let world = World::default();
+    let ray = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 1.0, 0.0));
+
+    assert_eq!(world.path_trace(&ray, &mut Rng::new(0)), Color::black());
+}
+
+#[test]
+fn light_bounces_inside_a_glowing_sphere() {
+    // Inside a closed sphere that gives off E and reflects a fraction a of
+    // what reaches it, the light converges on E / (1 - a) everywhere. Only
+    // Russian roulette ends these paths, so this also checks it is unbiased.
+    let material = Material {
+        color: Color::white(),
+        diffuse: 0.5,
+        specular: 0.0,
+        emissive: color(0.25, 0.5, 0.25),
+        ..Material::default_material()
+    };
+    let mut world = World::new();
+    world.lights.clear();
+    world.add_object(Sphere::default().with_material(material));
+
+    let ray = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0));
+    let c = average_path(&world, ray, 4000);
+
+    assert!((c.r - 0.5).abs() < 0.03, "{:?}", c);
+    assert!((c.g - 1.0).abs() < 0.05, "{:?}", c);
+}
+
+#[test]
+fn path_tracing_bleeds_color_between_surfaces() {
+    // A white floor beside a red wall, lit from the open side
+    let white = Material {
+        color: Color::white(),
+        specular: 0.0,
+        ..Material::default_material()
+    };
+    let red = Material {
+        color: color(1.0, 0.0, 0.0),
+        ..white.clone()
+    };
+    let mut world = World::new();
+    world.lights = vec![PointLight {
+        position: point(-5.0, 5.0, 0.0),
+        intensity: Color::white(),
+    }
+    .into()];
+    world.add_object(Plane::default().with_material(white));
+    world.add_object(
+        Plane::default()
+            .with_transform(
+                Matrix::identity()
+                    .rotate_z(PI / 2.0)
+                    .translate(1.0, 0.0, 0.0),
+            )
+            .with_material(red),
+    );
+
+    let ray = Ray::new(point(0.5, 1.0, 0.0), vector(0.0, -1.0, 0.0));
+    let whitted = world.color(&ray);
+    let traced = average_path(&world, ray, 500);
+
+    assert_eq!(whitted.r, whitted.g);
+    assert!(traced.r > traced.g * 1.2, "{:?}", traced);
+}
+
+#[test]
+fn total_internal_reflection_keeps_the_path_going() {
+    // This far off center, a ray inside a glass sphere always meets the
+    // surface beyond the critical angle, so it keeps picking up the glow.
+    let material = Material {
+        color: Color::black(),
+        diffuse: 0.0,
+        specular: 0.0,
+        transparency: 1.0,
+        refractive_index: 1.5,
+        emissive: color(0.1, 0.1, 0.1),
+        ..Material::default_material()
+    };
+    let mut world = World::new();
+    world.lights.clear();
+    world.add_object(Sphere::default().with_material(material));
+
+    let ray = Ray::new(point(0.0, 0.9, 0.0), vector(0.0, 0.0, 1.0));
+    let c = world.path_trace(&ray, &mut Rng::new(0));
+
+    assert!(c.r > 1.0, "{:?}", c);
+}
@@ -4,67 +4,81 @@ use super::shape::*;
 use super::sphere::*;
 use super::tuple::*;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Intersection {
     pub t: f64,
     pub object: Shape,
+    pub u: f64,
+    pub v: f64,
 }
 
 impl Intersection {
     pub fn new(t: f64, s: Shape) -> Intersection {
-        Intersection { t, object: s }
+        Intersection {
+            t,
+            object: s,
+            u: 0.0,
+            v: 0.0,
+        }
+    }
+
+    /// An intersection that also records where on the surface it happened, as
+    /// barycentric coordinates for triangles.
+    pub fn with_uv(t: f64, s: Shape, u: f64, v: f64) -> Intersection {
+        Intersection { t, object: s, u, v }
     }
 }
 
-pub fn hit(hits: Vec<Intersection>) -> Option<Intersection> {
+pub fn hit(hits: &[Intersection]) -> Option<Intersection> {
     // hits.reduce(|a, b| if a.t < b.t { a } else { b })
     hits.iter()
         .filter(|a| a.t >= 0.0)
-        .fold(None, |acc, &b| match acc {
+        .fold(None, |acc: Option<&Intersection>, b| match acc {
             None => Some(b),
             Some(a) if b.t < a.t => Some(b),
             _ => acc,
         })
+        .cloned()
 }
 
 #[test]
 fn all_positive_t() {
-    let s = Sphere::default().into();
-    let i1 = Intersection::new(1.0, s);
-    let i2 = Intersection::new(2.0, s);
-    let hit = hit(vec![i1, i2]);
+    let s: Shape = Sphere::default().into();
+    let i1 = Intersection::new(1.0, s.clone());
+    let i2 = Intersection::new(2.0, s.clone());
+    let hit = hit(&[i1.clone(), i2]);
 
     assert_eq!(hit, Some(i1));
 }
 
 #[test]
 fn some_negative_t() {
-    let s = Sphere::default().into();
-    let i1 = Intersection::new(-1.0, s);
-    let i2 = Intersection::new(1.0, s);
-    let hit = hit(vec![i1, i2]);
+    let s: Shape = Sphere::default().into();
+    let i1 = Intersection::new(-1.0, s.clone());
+    let i2 = Intersection::new(1.0, s.clone());
+    let hit = hit(&[i1, i2.clone()]);
 
     assert_eq!(hit, Some(i2));
 }
 
 #[test]
 fn all_negative_t() {
-    let s = Sphere::default().into();
-    let i1 = Intersection::new(-2.0, s);
-    let i2 = Intersection::new(-1.0, s);
-    let hit = hit(vec![i1, i2]);
+    let s: Shape = Sphere::default().into();
+    let i1 = Intersection::new(-2.0, s.clone());
+    let i2 = Intersection::new(-1.0, s.clone());
+    let hit = hit(&[i1, i2]);
 
     assert_eq!(hit, None);
 }
 
 #[test]
 fn randome_order_t() {
-    let s = Sphere::default().into();
-    let i1 = Intersection::new(5.0, s);
-    let i2 = Intersection::new(7.0, s);
-    let i3 = Intersection::new(-3.0, s);
-    let i4 = Intersection::new(2.0, s);
-    let hit = hit(vec![i1, i2, i3, i4]);
+    let s: Shape = Sphere::default().into();
+    let i1 = Intersection::new(5.0, s.clone());
+    let i2 = Intersection::new(7.0, s.clone());
+    let i3 = Intersection::new(-3.0, s.clone());
+    let i4 = Intersection::new(2.0, s.clone());
+    let hit = hit(&[i1, i2, i3, i4.clone()]);
 
     assert_eq!(hit, Some(i4));
 }
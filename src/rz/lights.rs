@@ -1,8 +1,324 @@
 use super::color::*;
+use super::rng::*;
 use super::tuple::*;
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Light {
+    Point(PointLight),
+    Area(AreaLight),
+    Directional(DirectionalLight),
+    Spot(SpotLight),
+}
+
+/// One direction from a shaded point toward a light, and how far away the light is
+/// along it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LightSample {
+    pub direction: Tuple,
+    pub distance: f64,
+}
+
+impl LightSample {
+    fn toward(position: Tuple, point: Tuple) -> Self {
+        let v = position - point;
+        LightSample {
+            direction: v.normalized(),
+            distance: v.magnitude(),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct PointLight {
     pub position: Tuple,
     pub intensity: Color,
 }
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Light::Point(light)
+    }
+}
+
+/// A rectangular light made of `usteps` by `vsteps` cells, each sampled once.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AreaLight {
+    pub corner: Tuple,
+    pub uvec: Tuple,
+    pub usteps: usize,
+    pub vvec: Tuple,
+    pub vsteps: usize,
+    pub position: Tuple,
+    pub intensity: Color,
+    pub jitter: bool,
+}
+
+impl From<AreaLight> for Light {
+    fn from(light: AreaLight) -> Self {
+        Light::Area(light)
+    }
+}
+
+impl AreaLight {
+    pub fn new(
+        corner: Tuple,
+        full_uvec: Tuple,
+        usteps: usize,
+        full_vvec: Tuple,
+        vsteps: usize,
+        intensity: Color,
+    ) -> Self {
+        assert!(
+            usteps > 0 && vsteps > 0,
+            "an area light needs at least one step each way"
+        );
+
+        AreaLight {
+            corner,
+            uvec: full_uvec / usteps as f64,
+            usteps,
+            vvec: full_vvec / vsteps as f64,
+            vsteps,
+            position: corner + full_uvec / 2.0 + full_vvec / 2.0,
+            intensity,
+            jitter: true,
+        }
+    }
+
+    pub fn samples(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    /// A point inside cell (u, v), at its center or jittered within it.
+    pub fn point_on_light(&self, u: usize, v: usize, rng: &mut Rng) -> Tuple {
+        let (du, dv) = if self.jitter {
+            (rng.next_f64(), rng.next_f64())
+        } else {
+            (0.5, 0.5)
+        };
+
+        self.corner + self.uvec * (u as f64 + du) + self.vvec * (v as f64 + dv)
+    }
+}
+
+/// A light infinitely far away, like the sun, whose rays all travel in `direction`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DirectionalLight {
+    pub direction: Tuple,
+    pub intensity: Color,
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(light: DirectionalLight) -> Self {
+        Light::Directional(light)
+    }
+}
+
+/// A point light restricted to a cone around `direction`. Points within
+/// `inner_angle` of the axis get the full intensity, which falls off smoothly to
+/// nothing at `outer_angle`. Both angles are measured from the axis, in radians.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpotLight {
+    pub position: Tuple,
+    pub direction: Tuple,
+    pub inner_angle: f64,
+    pub outer_angle: f64,
+    pub intensity: Color,
+}
+
+impl From<SpotLight> for Light {
+    fn from(light: SpotLight) -> Self {
+        Light::Spot(light)
+    }
+}
+
+impl SpotLight {
+    pub fn falloff(&self, point: Tuple) -> f64 {
+        let cos = (point - self.position)
+            .normalized()
+            .dot(self.direction.normalized());
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+
+        if cos >= cos_inner {
+            1.0
+        } else if cos <= cos_outer {
+            0.0
+        } else {
+            let x = (cos - cos_outer) / (cos_inner - cos_outer);
+            x * x * (3.0 - 2.0 * x)
+        }
+    }
+}
+
+impl Light {
+    pub fn intensity(&self) -> Color {
+        match *self {
+            Light::Point(light) => light.intensity,
+            Light::Area(light) => light.intensity,
+            Light::Directional(light) => light.intensity,
+            Light::Spot(light) => light.intensity,
+        }
+    }
+
+    /// The intensity of the light arriving at `point`, ignoring shadows.
+    pub fn intensity_toward(&self, point: Tuple) -> Color {
+        match *self {
+            Light::Spot(light) => light.intensity * light.falloff(point),
+            _ => self.intensity(),
+        }
+    }
+
+    /// The directions to sample when lighting `point`.
+    pub fn samples(&self, point: Tuple) -> Vec<LightSample> {
+        match *self {
+            Light::Point(light) => vec![LightSample::toward(light.position, point)],
+            Light::Spot(light) => vec![LightSample::toward(light.position, point)],
+            Light::Directional(light) => vec![LightSample {
+                direction: -light.direction.normalized(),
+                distance: f64::INFINITY,
+            }],
+            Light::Area(light) => {
+                let mut rng = Rng::from_values(0, &[point.x, point.y, point.z]);
+                let mut samples = Vec::with_capacity(light.samples());
+                for v in 0..light.vsteps {
+                    for u in 0..light.usteps {
+                        let position = light.point_on_light(u, v, &mut rng);
+                        samples.push(LightSample::toward(position, point));
+                    }
+                }
+
+                samples
+            }
+        }
+    }
+}
+
+#[test]
+fn creating_an_area_light() {
+    let light = AreaLight::new(
+        point(0.0, 0.0, 0.0),
+        vector(2.0, 0.0, 0.0),
+        4,
+        vector(0.0, 0.0, 1.0),
+        2,
+        Color::white(),
+    );
+
+    assert_eq!(light.uvec, vector(0.5, 0.0, 0.0));
+    assert_eq!(light.vvec, vector(0.0, 0.0, 0.5));
+    assert_eq!(light.samples(), 8);
+    assert_eq!(light.position, point(1.0, 0.0, 0.5));
+}
+
+#[test]
+#[should_panic(expected = "at least one step")]
+fn area_light_needs_steps() {
+    AreaLight::new(
+        point(0.0, 0.0, 0.0),
+        vector(2.0, 0.0, 0.0),
+        0,
+        vector(0.0, 0.0, 1.0),
+        2,
+        Color::white(),
+    );
+}
+
+#[test]
+fn finding_cell_centers_on_an_area_light() {
+    let mut light = AreaLight::new(
+        point(0.0, 0.0, 0.0),
+        vector(2.0, 0.0, 0.0),
+        4,
+        vector(0.0, 0.0, 1.0),
+        2,
+        Color::white(),
+    );
+    light.jitter = false;
+    let mut rng = Rng::new(0);
+
+    let cases = [
+        (0, 0, point(0.25, 0.0, 0.25)),
+        (1, 0, point(0.75, 0.0, 0.25)),
+        (0, 1, point(0.25, 0.0, 0.75)),
+        (2, 0, point(1.25, 0.0, 0.25)),
+        (3, 1, point(1.75, 0.0, 0.75)),
+    ];
+
+    for (u, v, expected) in cases {
+        assert_eq!(light.point_on_light(u, v, &mut rng), expected);
+    }
+}
+
+#[test]
+fn jittered_points_stay_inside_their_cell() {
+    let light = AreaLight::new(
+        point(0.0, 0.0, 0.0),
+        vector(2.0, 0.0, 0.0),
+        4,
+        vector(0.0, 0.0, 1.0),
+        2,
+        Color::white(),
+    );
+    let mut rng = Rng::new(3);
+
+    for _ in 0..100 {
+        let p = light.point_on_light(2, 1, &mut rng);
+        assert!(p.x >= 1.0 && p.x <= 1.5);
+        assert!(p.z >= 0.5 && p.z <= 1.0);
+        assert_eq!(p.y, 0.0);
+    }
+}
+
+#[test]
+fn directional_light_samples_have_no_distance() {
+    let light: Light = DirectionalLight {
+        direction: vector(0.0, -2.0, 0.0),
+        intensity: Color::white(),
+    }
+    .into();
+    let samples = light.samples(point(5.0, 0.0, 5.0));
+
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].direction, vector(0.0, 1.0, 0.0));
+    assert_eq!(samples[0].distance, f64::INFINITY);
+}
+
+#[test]
+fn spot_light_falls_off_between_cone_angles() {
+    let light = SpotLight {
+        position: point(0.0, 10.0, 0.0),
+        direction: vector(0.0, -1.0, 0.0),
+        inner_angle: std::f64::consts::PI / 8.0,
+        outer_angle: std::f64::consts::PI / 4.0,
+        intensity: Color::white(),
+    };
+
+    assert_eq!(light.falloff(point(0.0, 0.0, 0.0)), 1.0);
+    assert_eq!(light.falloff(point(11.0, 0.0, 0.0)), 0.0);
+    assert_eq!(light.falloff(point(0.0, 20.0, 0.0)), 0.0);
+
+    let partial = light.falloff(point(6.0, 0.0, 0.0));
+    assert!(partial > 0.0 && partial < 1.0);
+}
+
+#[test]
+fn spot_light_intensity_toward_point() {
+    let light: Light = SpotLight {
+        position: point(0.0, 10.0, 0.0),
+        direction: vector(0.0, -1.0, 0.0),
+        inner_angle: std::f64::consts::PI / 8.0,
+        outer_angle: std::f64::consts::PI / 4.0,
+        intensity: color(0.5, 0.5, 0.5),
+    }
+    .into();
+
+    assert_eq!(
+        light.intensity_toward(point(0.0, 0.0, 1.0)),
+        color(0.5, 0.5, 0.5)
+    );
+    assert_eq!(
+        light.intensity_toward(point(20.0, 0.0, 0.0)),
+        Color::black()
+    );
+}
@@ -2,15 +2,25 @@
 
 use super::color::*;
 use super::lights::*;
+use super::matrix::*;
+use super::pattern::*;
+use super::shape::*;
+use super::sphere::*;
 use super::tuple::*;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Material {
     pub color: Color,
     pub ambient: f64,
     pub diffuse: f64,
     pub specular: f64,
     pub shininess: f64,
+    pub reflective: f64,
+    pub transparency: f64,
+    pub refractive_index: f64,
+    pub pattern: Option<Pattern>,
+    /// Light given off by the surface itself, independent of any light source.
+    pub emissive: Color,
 }
 
 impl Material {
@@ -27,69 +37,117 @@ impl Material {
             diffuse,
             specular,
             shininess,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            pattern: None,
+            emissive: Color::black(),
+        }
+    }
+
+    /// The surface color at `point` in world space, taken from the pattern
+    /// when there is one.
+    pub fn color_at(&self, object: &Shape, point: Tuple) -> Color {
+        match &self.pattern {
+            Some(pattern) => pattern.color_at(object.transform().inverse() * point),
+            None => self.color,
         }
     }
 
     pub fn lighting(
-        self,
-        light: PointLight,
+        &self,
+        object: &Shape,
+        light: &Light,
         point: Tuple,
         eye: Tuple,
         normal: Tuple,
-        in_shadow: bool,
+        intensity: f64,
     ) -> Color {
-        let effective_color = self.color * light.intensity;
+        let surface_color = self.color_at(object, point);
 
-        // Find the direction to the light source
-        let light_vector = (light.position - point).normalized();
+        let light_color = light.intensity_toward(point);
 
         // Compute the ambient contribution
-        let ambient = effective_color * self.ambient;
-
-        let diffuse: Color;
-        let specular: Color;
-        let light_dot_normal = light_vector.dot(normal);
-        // Cosine of the angle between light and normal
-        if light_dot_normal < 0.0 {
-            // Light is on the other side of the surface
-            diffuse = Color::black();
-            specular = Color::black();
-        } else {
-            diffuse = effective_color * self.diffuse * light_dot_normal;
+        let ambient = surface_color * light_color * self.ambient;
+        let effective_color = surface_color * light_color;
+
+        let samples = light.samples(point);
+        let mut sum = Color::black();
+        for sample in &samples {
+            // Direction to the light source
+            let light_vector = sample.direction;
+
+            let light_dot_normal = light_vector.dot(normal);
+            // Cosine of the angle between light and normal
+            if light_dot_normal < 0.0 {
+                // Light is on the other side of the surface
+                continue;
+            }
+
+            sum = sum + effective_color * self.diffuse * light_dot_normal;
 
             let reflect = -light_vector.reflected(normal);
             let reflect_dot_eye = reflect.dot(eye);
 
-            if reflect_dot_eye <= 0.0 {
-                specular = Color::black();
-            } else {
+            if reflect_dot_eye > 0.0 {
                 let factor = reflect_dot_eye.powf(self.shininess);
-                specular = light.intensity * self.specular * factor;
+                sum = sum + light_color * self.specular * factor;
             }
         }
 
-        if in_shadow {
-            ambient
-        } else {
-            ambient + diffuse + specular
-        }
+        ambient + sum / samples.len() as f64 * intensity
     }
 
     pub fn default_material() -> Material {
         Material::new(Color::new(1.0, 1.0, 1.0), 0.1, 0.9, 0.9, 200.0)
     }
+
+    pub fn with_pattern(self, pattern: Pattern) -> Material {
+        Material {
+            pattern: Some(pattern),
+            ..self
+        }
+    }
+
+    pub fn with_reflective(self, reflective: f64) -> Material {
+        Material { reflective, ..self }
+    }
+
+    pub fn with_transparency(self, transparency: f64, refractive_index: f64) -> Material {
+        Material {
+            transparency,
+            refractive_index,
+            ..self
+        }
+    }
+
+    pub fn with_emissive(self, emissive: Color) -> Material {
+        Material { emissive, ..self }
+    }
+}
+
+#[cfg(test)]
+fn object() -> Shape {
+    Sphere::default().into()
 }
 
 #[test]
 fn sphere_with_default_material() {
     let eye = vector(0.0, 0.0, -1.0);
     let normal = vector(0.0, 0.0, -1.0);
-    let light = PointLight {
+    let light: Light = PointLight {
         position: point(0.0, 0.0, -10.0),
         intensity: color(1.0, 1.0, 1.0),
-    };
-    let result =
-        Material::default_material().lighting(light, point(0.0, 0.0, 0.0), eye, normal, false);
+    }
+    .into();
+    let result = Material::default_material().lighting(
+        &object(),
+        &light,
+        point(0.0, 0.0, 0.0),
+        eye,
+        normal,
+        1.0,
+    );
 
     assert_eq!(result, Color::new(1.9, 1.9, 1.9));
 }
@@ -99,12 +157,19 @@ fn lighting_with_eye_between() {
     let s22 = 2.0_f64.sqrt() / 2.0;
     let eye = vector(0.0, s22, -s22);
     let normal = vector(0.0, 0.0, -1.0);
-    let light = PointLight {
+    let light: Light = PointLight {
         position: point(0.0, 0.0, -10.0),
         intensity: color(1.0, 1.0, 1.0),
-    };
-    let result =
-        Material::default_material().lighting(light, point(0.0, 0.0, 0.0), eye, normal, false);
+    }
+    .into();
+    let result = Material::default_material().lighting(
+        &object(),
+        &light,
+        point(0.0, 0.0, 0.0),
+        eye,
+        normal,
+        1.0,
+    );
 
     assert_eq!(result, Color::new(1.0, 1.0, 1.0));
 }
@@ -113,12 +178,19 @@ fn lighting_with_eye_between() {
 fn lighting_with_eye_opposite() {
     let eye = vector(0.0, 0.0, -1.0);
     let normal = vector(0.0, 0.0, -1.0);
-    let light = PointLight {
+    let light: Light = PointLight {
         position: point(0.0, 10.0, -10.0),
         intensity: color(1.0, 1.0, 1.0),
-    };
-    let result =
-        Material::default_material().lighting(light, point(0.0, 0.0, 0.0), eye, normal, false);
+    }
+    .into();
+    let result = Material::default_material().lighting(
+        &object(),
+        &light,
+        point(0.0, 0.0, 0.0),
+        eye,
+        normal,
+        1.0,
+    );
 
     assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
 }
@@ -128,12 +200,19 @@ fn lighting_with_eye_in_path() {
     let s22 = 2.0_f64.sqrt() / 2.0;
     let eye = vector(0.0, -s22, -s22);
     let normal = vector(0.0, 0.0, -1.0);
-    let light = PointLight {
+    let light: Light = PointLight {
         position: point(0.0, 10.0, -10.0),
         intensity: color(1.0, 1.0, 1.0),
-    };
-    let result =
-        Material::default_material().lighting(light, point(0.0, 0.0, 0.0), eye, normal, false);
+    }
+    .into();
+    let result = Material::default_material().lighting(
+        &object(),
+        &light,
+        point(0.0, 0.0, 0.0),
+        eye,
+        normal,
+        1.0,
+    );
 
     assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
 }
@@ -142,12 +221,19 @@ fn lighting_with_eye_in_path() {
 fn lighting_with_light_behind() {
     let eye = vector(0.0, 0.0, -1.0);
     let normal = vector(0.0, 0.0, -1.0);
-    let light = PointLight {
+    let light: Light = PointLight {
         position: point(0.0, 0.0, 10.0),
         intensity: color(1.0, 1.0, 1.0),
-    };
-    let result =
-        Material::default_material().lighting(light, point(0.0, 0.0, 0.0), eye, normal, false);
+    }
+    .into();
+    let result = Material::default_material().lighting(
+        &object(),
+        &light,
+        point(0.0, 0.0, 0.0),
+        eye,
+        normal,
+        1.0,
+    );
 
     assert_eq!(result, Color::new(0.1, 0.1, 0.1));
 }
@@ -156,11 +242,218 @@ fn lighting_with_light_behind() {
 fn lighting_with_surface_in_shadow() {
     let eye = vector(0.0, 0.0, -1.0);
     let normal = vector(0.0, 0.0, -1.0);
-    let light = PointLight {
+    let light: Light = PointLight {
         position: point(0.0, 0.0, -10.0),
         intensity: color(1.0, 1.0, 1.0),
-    };
-    let result =
-        Material::default_material().lighting(light, point(0.0, 0.0, 0.0), eye, normal, true);
+    }
+    .into();
+    let result = Material::default_material().lighting(
+        &object(),
+        &light,
+        point(0.0, 0.0, 0.0),
+        eye,
+        normal,
+        0.0,
+    );
     assert_eq!(result, Color::new(0.1, 0.1, 0.1));
 }
+
+#[test]
+fn default_material_is_not_reflective() {
+    let m = Material::default_material();
+    assert_eq!(m.reflective, 0.0);
+}
+
+#[test]
+fn default_material_is_opaque() {
+    let m = Material::default_material();
+    assert_eq!(m.transparency, 0.0);
+    assert_eq!(m.refractive_index, 1.0);
+}
+
+#[test]
+fn lighting_samples_the_area_light() {
+    let mut light = AreaLight::new(
+        point(-0.5, -0.5, -5.0),
+        vector(1.0, 0.0, 0.0),
+        2,
+        vector(0.0, 1.0, 0.0),
+        2,
+        Color::white(),
+    );
+    light.jitter = false;
+    let light: Light = light.into();
+    let material = Material {
+        diffuse: 0.9,
+        specular: 0.0,
+        ..Material::default_material()
+    };
+    let eye = point(0.0, 0.0, -5.0);
+
+    let p = point(0.0, 0.0, -1.0);
+    let result = material.lighting(
+        &object(),
+        &light,
+        p,
+        (eye - p).normalized(),
+        p - point(0.0, 0.0, 0.0),
+        1.0,
+    );
+    assert_eq!(result, color(0.9965, 0.9965, 0.9965));
+
+    let sq2 = 2_f64.sqrt() / 2.0;
+    let p = point(0.0, sq2, -sq2);
+    let result = material.lighting(
+        &object(),
+        &light,
+        p,
+        (eye - p).normalized(),
+        p - point(0.0, 0.0, 0.0),
+        1.0,
+    );
+    assert_eq!(result, color(0.62318, 0.62318, 0.62318));
+}
+
+#[test]
+fn lighting_scales_with_light_intensity_at_point() {
+    let light: Light = PointLight {
+        position: point(0.0, 0.0, -10.0),
+        intensity: Color::white(),
+    }
+    .into();
+    let material = Material {
+        specular: 0.0,
+        ..Material::default_material()
+    };
+    let p = point(0.0, 0.0, -1.0);
+    let eye = vector(0.0, 0.0, -1.0);
+    let normal = vector(0.0, 0.0, -1.0);
+
+    let cases = [(1.0, 1.0), (0.5, 0.55), (0.0, 0.1)];
+    for (intensity, expected) in cases {
+        let result = material.lighting(&object(), &light, p, eye, normal, intensity);
+        assert_eq!(result, color(expected, expected, expected));
+    }
+}
+
+#[test]
+fn lighting_with_directional_light() {
+    let eye = vector(0.0, 0.0, -1.0);
+    let normal = vector(0.0, 0.0, -1.0);
+    let light: Light = DirectionalLight {
+        direction: vector(0.0, 0.0, 1.0),
+        intensity: Color::white(),
+    }
+    .into();
+    let result = Material::default_material().lighting(
+        &object(),
+        &light,
+        point(0.0, 0.0, 0.0),
+        eye,
+        normal,
+        1.0,
+    );
+
+    assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+}
+
+#[test]
+fn lighting_outside_spot_light_cone() {
+    let eye = vector(0.0, 0.0, -1.0);
+    let normal = vector(0.0, 0.0, -1.0);
+    let light: Light = SpotLight {
+        position: point(0.0, 0.0, -10.0),
+        direction: vector(0.0, 1.0, 0.0),
+        inner_angle: 0.2,
+        outer_angle: 0.3,
+        intensity: Color::white(),
+    }
+    .into();
+    let result = Material::default_material().lighting(
+        &object(),
+        &light,
+        point(0.0, 0.0, 0.0),
+        eye,
+        normal,
+        1.0,
+    );
+
+    // Not even ambient light reaches outside the cone
+    assert_eq!(result, Color::black());
+}
+
+#[test]
+fn lighting_inside_spot_light_cone() {
+    let eye = vector(0.0, 0.0, -1.0);
+    let normal = vector(0.0, 0.0, -1.0);
+    let light: Light = SpotLight {
+        position: point(0.0, 0.0, -10.0),
+        direction: vector(0.0, 0.0, 1.0),
+        inner_angle: 0.2,
+        outer_angle: 0.3,
+        intensity: Color::white(),
+    }
+    .into();
+    let result = Material::default_material().lighting(
+        &object(),
+        &light,
+        point(0.0, 0.0, 0.0),
+        eye,
+        normal,
+        1.0,
+    );
+
+    assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+}
+
+#[test]
+fn lighting_with_a_pattern() {
+    let material = Material {
+        ambient: 1.0,
+        diffuse: 0.0,
+        specular: 0.0,
+        ..Material::default_material()
+    }
+    .with_pattern(Pattern::stripes(Color::white(), Color::black()));
+    let eye = vector(0.0, 0.0, -1.0);
+    let normal = vector(0.0, 0.0, -1.0);
+    let light: Light = PointLight {
+        position: point(0.0, 0.0, -10.0),
+        intensity: Color::white(),
+    }
+    .into();
+
+    let c1 = material.lighting(&object(), &light, point(0.9, 0.0, 0.0), eye, normal, 1.0);
+    let c2 = material.lighting(&object(), &light, point(1.1, 0.0, 0.0), eye, normal, 1.0);
+    assert_eq!(c1, Color::white());
+    assert_eq!(c2, Color::black());
+}
+
+#[test]
+fn pattern_follows_the_object_transform() {
+    let object: Shape = Sphere::default()
+        .with_transform(Matrix::scaling(2.0, 2.0, 2.0))
+        .into();
+    let material =
+        Material::default_material().with_pattern(Pattern::stripes(Color::white(), Color::black()));
+
+    assert_eq!(
+        material.color_at(&object, point(1.5, 0.0, 0.0)),
+        Color::white()
+    );
+}
+
+#[test]
+fn pattern_with_object_and_pattern_transforms() {
+    let object: Shape = Sphere::default()
+        .with_transform(Matrix::scaling(2.0, 2.0, 2.0))
+        .into();
+    let pattern = Pattern::stripes(Color::white(), Color::black())
+        .with_transform(Matrix::translation(0.5, 0.0, 0.0));
+    let material = Material::default_material().with_pattern(pattern);
+
+    assert_eq!(
+        material.color_at(&object, point(2.5, 0.0, 0.0)),
+        Color::white()
+    );
+}
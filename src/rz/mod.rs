@@ -1,27 +1,50 @@
+mod bounds;
+mod bvh;
 mod camera;
 mod canvas;
 mod color;
+mod cone;
+mod csg;
+mod cube;
+mod cylinder;
+#[cfg(test)]
+mod golden;
+mod group;
+mod integrator;
 mod intersection;
 mod lights;
 mod material;
 mod matrix;
+mod obj;
+mod pattern;
 mod plane;
+mod ppm;
 mod ray;
+mod rng;
+mod sampling;
+mod scene;
 mod shape;
+mod smooth_triangle;
 mod sphere;
+mod texture;
+mod tonemap;
+mod transform;
+mod triangle;
 mod tuple;
 mod world;
 
 pub use camera::*;
-pub use canvas::*;
 pub use color::*;
-pub use intersection::*;
+pub use integrator::*;
 pub use lights::*;
 pub use material::*;
 pub use matrix::*;
+pub use pattern::*;
 pub use plane::*;
-pub use ray::*;
-pub use shape::*;
+pub use sampling::*;
+pub use scene::*;
 pub use sphere::*;
+pub use tonemap::*;
+pub use transform::*;
 pub use tuple::*;
 pub use world::*;
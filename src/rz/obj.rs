@@ -0,0 +1,322 @@
+#![allow(unused_imports)]
+use super::group::*;
+use super::matrix::*;
+use super::shape::*;
+use super::smooth_triangle::*;
+use super::triangle::*;
+use super::tuple::*;
+use std::fs;
+use std::io;
+
+/// Geometry read from a Wavefront OBJ file.
+///
+/// Faces become `Triangle`s, or `SmoothTriangle`s when they reference vertex
+/// normals, and polygons are fanned out into triangles. Faces before the first
+/// `g` statement land in `default_group`. Anything the parser doesn't understand
+/// is skipped and counted in `ignored`.
+#[derive(Debug, Default)]
+pub struct ObjFile {
+    pub vertices: Vec<Tuple>,
+    pub normals: Vec<Tuple>,
+    pub default_group: Vec<Shape>,
+    pub groups: Vec<(String, Vec<Shape>)>,
+    pub ignored: usize,
+}
+
+impl ObjFile {
+    pub fn load(path: &str) -> io::Result<ObjFile> {
+        Ok(ObjFile::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn parse(source: &str) -> ObjFile {
+        let mut obj = ObjFile::default();
+
+        for line in source.lines() {
+            let mut words = line.split_whitespace();
+            let handled = match words.next() {
+                Some("v") => parse_tuple(words).map(|(x, y, z)| obj.vertices.push(point(x, y, z))),
+                Some("vn") => parse_tuple(words).map(|(x, y, z)| obj.normals.push(vector(x, y, z))),
+                Some("f") => obj.parse_face(words),
+                Some("g") => {
+                    let name = words.collect::<Vec<_>>().join(" ");
+                    obj.groups.push((name, vec![]));
+                    Some(())
+                }
+                // Blank lines and comments aren't worth reporting
+                None => Some(()),
+                Some(word) if word.starts_with('#') => Some(()),
+                _ => None,
+            };
+
+            if handled.is_none() {
+                obj.ignored += 1;
+            }
+        }
+
+        obj
+    }
+
+    /// The shapes in the named group, if there is one.
+    pub fn group(&self, name: &str) -> Option<&Vec<Shape>> {
+        self.groups
+            .iter()
+            .find(|(group, _)| group == name)
+            .map(|(_, shapes)| shapes)
+    }
+
+    /// Every triangle in the file, ready to add to a `World`.
+    pub fn shapes(&self) -> Vec<Shape> {
+        let mut shapes = self.default_group.clone();
+        for (_, group) in &self.groups {
+            shapes.extend(group.iter().cloned());
+        }
+
+        shapes
+    }
+
+    /// The whole file as one group, with each named group as a child group, so the
+    /// mesh can be placed in a `World` with a single transform.
+    pub fn to_group(&self) -> Group {
+        let mut group = Group::default();
+        for shape in &self.default_group {
+            group.add_child(shape.clone());
+        }
+        for (_, shapes) in &self.groups {
+            group.add_child(Group::new(Matrix::identity(), shapes.clone()));
+        }
+
+        group
+    }
+
+    fn parse_face<'a>(&mut self, words: impl Iterator<Item = &'a str>) -> Option<()> {
+        let corners = words
+            .map(|word| self.parse_corner(word))
+            .collect::<Option<Vec<_>>>()?;
+        if corners.len() < 3 {
+            return None;
+        }
+
+        let mut triangles: Vec<Shape> = vec![];
+        for i in 1..corners.len() - 1 {
+            let (p1, n1) = corners[0];
+            let (p2, n2) = corners[i];
+            let (p3, n3) = corners[i + 1];
+
+            triangles.push(match (n1, n2, n3) {
+                (Some(n1), Some(n2), Some(n3)) => {
+                    SmoothTriangle::new(p1, p2, p3, n1, n2, n3).into()
+                }
+                _ => Triangle::new(p1, p2, p3).into(),
+            });
+        }
+
+        match self.groups.last_mut() {
+            Some((_, group)) => group.extend(triangles),
+            None => self.default_group.extend(triangles),
+        }
+
+        Some(())
+    }
+
+    /// Parses one `v`, `v/vt`, `v//vn` or `v/vt/vn` reference from a face.
+    fn parse_corner(&self, word: &str) -> Option<(Tuple, Option<Tuple>)> {
+        let mut parts = word.split('/');
+        let vertex = lookup(&self.vertices, parts.next()?)?;
+        let _texture = parts.next();
+        let normal = match parts.next() {
+            Some(index) => Some(lookup(&self.normals, index)?),
+            None => None,
+        };
+
+        Some((vertex, normal))
+    }
+}
+
+fn parse_tuple<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<(f64, f64, f64)> {
+    let x = words.next()?.parse().ok()?;
+    let y = words.next()?.parse().ok()?;
+    let z = words.next()?.parse().ok()?;
+
+    Some((x, y, z))
+}
+
+/// Resolves a 1-based OBJ index, or a negative one counting back from the end.
+fn lookup(items: &[Tuple], index: &str) -> Option<Tuple> {
+    let index: i64 = index.parse().ok()?;
+    let i = if index < 0 {
+        items.len() as i64 + index
+    } else {
+        index - 1
+    };
+
+    if i < 0 {
+        return None;
+    }
+
+    items.get(i as usize).copied()
+}
+
+#[test]
+fn ignoring_unrecognized_lines() {
+    let gibberish = "There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.";
+    let obj = ObjFile::parse(gibberish);
+
+    assert_eq!(obj.ignored, 5);
+}
+
+#[test]
+fn vertex_records() {
+    let source = "v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0";
+    let obj = ObjFile::parse(source);
+
+    assert_eq!(obj.vertices[0], point(-1.0, 1.0, 0.0));
+    assert_eq!(obj.vertices[1], point(-1.0, 0.5, 0.0));
+    assert_eq!(obj.vertices[2], point(1.0, 0.0, 0.0));
+    assert_eq!(obj.vertices[3], point(1.0, 1.0, 0.0));
+    assert_eq!(obj.ignored, 0);
+}
+
+#[test]
+fn parsing_triangle_faces() {
+    let source = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+f 1 3 4";
+    let obj = ObjFile::parse(source);
+
+    let t1 = Triangle::new(obj.vertices[0], obj.vertices[1], obj.vertices[2]);
+    let t2 = Triangle::new(obj.vertices[0], obj.vertices[2], obj.vertices[3]);
+    assert_eq!(obj.default_group, vec![t1.into(), t2.into()]);
+}
+
+#[test]
+fn triangulating_polygons() {
+    let source = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+f 1 2 3 4 5";
+    let obj = ObjFile::parse(source);
+    let v = &obj.vertices;
+
+    let expected: Vec<Shape> = vec![
+        Triangle::new(v[0], v[1], v[2]).into(),
+        Triangle::new(v[0], v[2], v[3]).into(),
+        Triangle::new(v[0], v[3], v[4]).into(),
+    ];
+    assert_eq!(obj.default_group, expected);
+}
+
+#[test]
+fn triangles_in_named_groups() {
+    let source = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+    let obj = ObjFile::parse(source);
+    let v = &obj.vertices;
+
+    assert_eq!(
+        obj.group("FirstGroup"),
+        Some(&vec![Triangle::new(v[0], v[1], v[2]).into()])
+    );
+    assert_eq!(
+        obj.group("SecondGroup"),
+        Some(&vec![Triangle::new(v[0], v[2], v[3]).into()])
+    );
+    assert_eq!(obj.shapes().len(), 2);
+}
+
+#[test]
+fn vertex_normal_records() {
+    let source = "vn 0 0 1
+vn 0.707 0 -0.707
+vn 1 2 3";
+    let obj = ObjFile::parse(source);
+
+    assert_eq!(obj.normals[0], vector(0.0, 0.0, 1.0));
+    assert_eq!(obj.normals[1], vector(0.707, 0.0, -0.707));
+    assert_eq!(obj.normals[2], vector(1.0, 2.0, 3.0));
+}
+
+#[test]
+fn faces_with_normals() {
+    let source = "v 0 1 0
+v -1 0 0
+v 1 0 0
+
+vn -1 0 0
+vn 1 0 0
+vn 0 1 0
+
+f 1//3 2//1 3//2
+f 1/0/3 2/102/1 3/14/2";
+    let obj = ObjFile::parse(source);
+    let (v, n) = (&obj.vertices, &obj.normals);
+
+    let expected: Shape = SmoothTriangle::new(v[0], v[1], v[2], n[2], n[0], n[1]).into();
+    assert_eq!(obj.default_group, vec![expected.clone(), expected]);
+}
+
+#[test]
+fn bad_faces_are_counted_not_fatal() {
+    let source = "v 0 1 0
+v -1 0 0
+v 1 0 0
+f 1 2 7
+f 1 2
+f 1 2 3
+vt 0.5 0.5";
+    let obj = ObjFile::parse(source);
+
+    assert_eq!(obj.default_group.len(), 1);
+    assert_eq!(obj.ignored, 3);
+}
+
+#[test]
+fn converting_obj_file_to_a_group() {
+    let source = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+    let obj = ObjFile::parse(source);
+    let group = obj.to_group();
+
+    assert_eq!(group.children().len(), 3);
+    assert_eq!(group.children()[0], obj.default_group[0]);
+    assert_eq!(
+        group.children()[1],
+        Group::new(Matrix::identity(), obj.group("FirstGroup").unwrap().clone()).into()
+    );
+    assert_eq!(
+        group.children()[2],
+        Group::new(
+            Matrix::identity(),
+            obj.group("SecondGroup").unwrap().clone()
+        )
+        .into()
+    );
+}
@@ -0,0 +1,161 @@
+#![allow(unused_imports)]
+use super::color::*;
+use super::matrix::*;
+use super::texture::*;
+use super::transform::*;
+use super::tuple::*;
+
+/// A color that varies over the surface of an object.
+///
+/// Patterns are evaluated in their own space: the point being shaded is taken
+/// into object space by the object's transform, then into pattern space by
+/// `transform`, so a pattern moves, scales and rotates along with its object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub transform: Transform,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternKind {
+    /// Alternates between the two colors every unit along x.
+    Stripes(Color, Color),
+    /// Blends linearly from the first color to the second between x = 0 and 1.
+    Gradient(Color, Color),
+    /// Concentric rings around the y axis, one unit apart.
+    Rings(Color, Color),
+    /// A 3D checkerboard of unit cubes.
+    Checkers(Color, Color),
+    /// An image wrapped around the object.
+    Texture(Texture),
+}
+
+impl Pattern {
+    pub fn new(kind: PatternKind) -> Self {
+        Pattern {
+            kind,
+            transform: Transform::identity(),
+        }
+    }
+
+    pub fn stripes(a: Color, b: Color) -> Self {
+        Pattern::new(PatternKind::Stripes(a, b))
+    }
+
+    pub fn gradient(a: Color, b: Color) -> Self {
+        Pattern::new(PatternKind::Gradient(a, b))
+    }
+
+    pub fn rings(a: Color, b: Color) -> Self {
+        Pattern::new(PatternKind::Rings(a, b))
+    }
+
+    pub fn checkers(a: Color, b: Color) -> Self {
+        Pattern::new(PatternKind::Checkers(a, b))
+    }
+
+    pub fn texture(texture: Texture) -> Self {
+        Pattern::new(PatternKind::Texture(texture))
+    }
+
+    pub fn with_transform(self, transform: impl Into<Transform>) -> Self {
+        Pattern {
+            transform: transform.into(),
+            ..self
+        }
+    }
+
+    /// The color at a point in object space.
+    pub fn color_at(&self, object_point: Tuple) -> Color {
+        let p = self.transform.inverse() * object_point;
+
+        match &self.kind {
+            &PatternKind::Stripes(a, b) => {
+                if (p.x.floor() as i64).rem_euclid(2) == 0 {
+                    a
+                } else {
+                    b
+                }
+            }
+            &PatternKind::Gradient(a, b) => a + (b - a) * (p.x - p.x.floor()),
+            &PatternKind::Rings(a, b) => {
+                let distance = (p.x * p.x + p.z * p.z).sqrt();
+                if (distance.floor() as i64).rem_euclid(2) == 0 {
+                    a
+                } else {
+                    b
+                }
+            }
+            &PatternKind::Checkers(a, b) => {
+                let sum = p.x.floor() + p.y.floor() + p.z.floor();
+                if (sum as i64).rem_euclid(2) == 0 {
+                    a
+                } else {
+                    b
+                }
+            }
+            PatternKind::Texture(texture) => texture.color_at(p),
+        }
+    }
+}
+
+#[test]
+fn stripes_alternate_in_x() {
+    let pattern = Pattern::stripes(Color::white(), Color::black());
+
+    assert_eq!(pattern.color_at(point(0.0, 0.0, 0.0)), Color::white());
+    assert_eq!(pattern.color_at(point(0.0, 1.0, 2.0)), Color::white());
+    assert_eq!(pattern.color_at(point(0.9, 0.0, 0.0)), Color::white());
+    assert_eq!(pattern.color_at(point(1.0, 0.0, 0.0)), Color::black());
+    assert_eq!(pattern.color_at(point(-0.1, 0.0, 0.0)), Color::black());
+    assert_eq!(pattern.color_at(point(-1.0, 0.0, 0.0)), Color::black());
+    assert_eq!(pattern.color_at(point(-1.1, 0.0, 0.0)), Color::white());
+}
+
+#[test]
+fn gradient_interpolates_between_colors() {
+    let pattern = Pattern::gradient(Color::white(), Color::black());
+
+    assert_eq!(pattern.color_at(point(0.0, 0.0, 0.0)), Color::white());
+    assert_eq!(
+        pattern.color_at(point(0.25, 0.0, 0.0)),
+        color(0.75, 0.75, 0.75)
+    );
+    assert_eq!(pattern.color_at(point(0.5, 0.0, 0.0)), color(0.5, 0.5, 0.5));
+    assert_eq!(
+        pattern.color_at(point(0.75, 0.0, 0.0)),
+        color(0.25, 0.25, 0.25)
+    );
+}
+
+#[test]
+fn rings_extend_in_x_and_z() {
+    let pattern = Pattern::rings(Color::white(), Color::black());
+
+    assert_eq!(pattern.color_at(point(0.0, 0.0, 0.0)), Color::white());
+    assert_eq!(pattern.color_at(point(1.0, 0.0, 0.0)), Color::black());
+    assert_eq!(pattern.color_at(point(0.0, 0.0, 1.0)), Color::black());
+    assert_eq!(pattern.color_at(point(0.708, 0.0, 0.708)), Color::black());
+}
+
+#[test]
+fn checkers_repeat_in_every_axis() {
+    let pattern = Pattern::checkers(Color::white(), Color::black());
+
+    assert_eq!(pattern.color_at(point(0.0, 0.0, 0.0)), Color::white());
+    assert_eq!(pattern.color_at(point(0.99, 0.0, 0.0)), Color::white());
+    assert_eq!(pattern.color_at(point(1.01, 0.0, 0.0)), Color::black());
+    assert_eq!(pattern.color_at(point(0.0, 0.99, 0.0)), Color::white());
+    assert_eq!(pattern.color_at(point(0.0, 1.01, 0.0)), Color::black());
+    assert_eq!(pattern.color_at(point(0.0, 0.0, 0.99)), Color::white());
+    assert_eq!(pattern.color_at(point(0.0, 0.0, 1.01)), Color::black());
+}
+
+#[test]
+fn pattern_with_its_own_transform() {
+    let pattern = Pattern::stripes(Color::white(), Color::black())
+        .with_transform(Matrix::scaling(2.0, 2.0, 2.0));
+
+    assert_eq!(pattern.color_at(point(1.5, 0.0, 0.0)), Color::white());
+    assert_eq!(pattern.color_at(point(2.5, 0.0, 0.0)), Color::black());
+}
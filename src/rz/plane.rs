@@ -1,16 +1,18 @@
 #![allow(unused_imports)]
+use super::bounds::*;
 use super::intersection::*;
 use super::material::*;
 use super::matrix::*;
 use super::ray::*;
 use super::shape::*;
+use super::transform::*;
 use super::tuple::*;
 use std::f64::consts::PI;
 const EPSILON: f64 = 0.00001;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Plane {
-    pub transform: Matrix<4>,
+    pub transform: Transform,
     pub material: Material,
 }
 
@@ -23,14 +25,14 @@ impl From<Plane> for Shape {
 impl Plane {
     pub fn new(transform: Matrix<4>, material: Material) -> Self {
         Self {
-            transform,
+            transform: transform.into(),
             material,
         }
     }
 
     pub fn default() -> Self {
         Self {
-            transform: Matrix::identity(),
+            transform: Transform::identity(),
             material: Material::default_material(),
         }
     }
@@ -39,10 +41,17 @@ impl Plane {
         vector(0.0, 1.0, 0.0)
     }
 
-    pub fn with_transform(&self, transform: Matrix<4>) -> Self {
+    pub fn bounds(&self) -> Bounds {
+        Bounds::new(
+            point(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            point(f64::INFINITY, 0.0, f64::INFINITY),
+        )
+    }
+
+    pub fn with_transform(&self, transform: impl Into<Transform>) -> Self {
         Self {
-            transform,
-            material: self.material,
+            transform: transform.into(),
+            material: self.material.clone(),
         }
     }
 
@@ -60,10 +69,7 @@ impl Plane {
 
         let t = -ray.origin.y / ray.direction.y;
 
-        vec![Intersection {
-            t,
-            object: (*self).into(),
-        }]
+        vec![Intersection::new(t, self.clone().into())]
     }
 }
 
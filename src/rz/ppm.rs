@@ -0,0 +1,288 @@
+#![allow(unused_imports)]
+use super::canvas::*;
+use super::color::*;
+use super::tonemap::*;
+use std::io;
+
+/// Longest line allowed in a plain PPM file.
+const PPM_LINE_LENGTH: usize = 70;
+
+/// Reading and writing the Netpbm PPM format: plain text `P3` and binary `P6`.
+impl Canvas {
+    /// The canvas as a plain `P3` PPM file. Each row of pixels starts on a new
+    /// line, and lines are wrapped so none is longer than 70 characters.
+    pub fn to_ppm(&self, tone_mapping: &ToneMapping) -> String {
+        let mut ppm = format!("P3\n{} {}\n255\n", self.width, self.height);
+
+        for y in 0..self.height {
+            let mut line = String::new();
+            for x in 0..self.width {
+                for value in self.ppm_values(x, y, tone_mapping) {
+                    let value = value.to_string();
+                    if !line.is_empty() && line.len() + 1 + value.len() > PPM_LINE_LENGTH {
+                        ppm.push_str(&line);
+                        ppm.push('\n');
+                        line.clear();
+                    }
+                    if !line.is_empty() {
+                        line.push(' ');
+                    }
+                    line.push_str(&value);
+                }
+            }
+            ppm.push_str(&line);
+            ppm.push('\n');
+        }
+
+        ppm
+    }
+
+    /// The canvas as a binary `P6` PPM file.
+    pub fn to_ppm_binary(&self, tone_mapping: &ToneMapping) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                ppm.extend(self.ppm_values(x, y, tone_mapping));
+            }
+        }
+
+        ppm
+    }
+
+    /// Reads a `P3` or `P6` PPM file, scaling its values by the file's maximum
+    /// so channels run from 0.0 to 1.0.
+    pub fn from_ppm(data: &[u8]) -> io::Result<Canvas> {
+        let mut reader = PpmReader { data, pos: 0 };
+
+        let magic = reader.token()?;
+        let binary = match magic.as_str() {
+            "P3" => false,
+            "P6" => true,
+            _ => return Err(invalid(format!("unsupported PPM type '{}'", magic))),
+        };
+        let width = reader.number()?;
+        let height = reader.number()?;
+        if width == 0 || height == 0 {
+            return Err(invalid(format!("invalid size {}x{}", width, height)));
+        }
+        let max = reader.number()?;
+        if max == 0 || max > 65535 {
+            return Err(invalid(format!("invalid maximum value {}", max)));
+        }
+
+        // A single whitespace byte separates the header from binary data.
+        if binary {
+            reader.pos += 1;
+        }
+
+        // Every value takes at least one byte, so a header claiming more pixels
+        // than the data could hold is corrupt; check before allocating for it.
+        let values = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3));
+        match values {
+            Some(values) if values <= data.len().saturating_sub(reader.pos) => {}
+            _ => {
+                return Err(invalid(format!(
+                    "{}x{} pixels is more than the file holds",
+                    width, height
+                )))
+            }
+        }
+
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut channel = || {
+                    let value = match (binary, max) {
+                        (false, _) => reader.number()?,
+                        (true, 0..=255) => reader.bytes(1)?[0] as usize,
+                        (true, _) => {
+                            let bytes = reader.bytes(2)?;
+                            (bytes[0] as usize) << 8 | bytes[1] as usize
+                        }
+                    };
+                    Ok::<f64, io::Error>(value as f64 / max as f64)
+                };
+                let color = Color::new(channel()?, channel()?, channel()?);
+                canvas.write(x, y, color);
+            }
+        }
+
+        Ok(canvas)
+    }
+
+    /// The pixel at `(x, y)` as three values from 0 to 255.
+    fn ppm_values(&self, x: usize, y: usize, tone_mapping: &ToneMapping) -> [u8; 3] {
+        let c = tone_mapping.apply(self.pixel_at(x, y));
+        [c.r, c.g, c.b].map(|v| (v * 255.0).round() as u8)
+    }
+}
+
+struct PpmReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl PpmReader<'_> {
+    /// The next whitespace separated word, skipping `#` comments.
+    fn token(&mut self) -> io::Result<String> {
+        loop {
+            match self.data.get(self.pos) {
+                Some(b'#') => {
+                    while self.data.get(self.pos).is_some_and(|&b| b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(_) => break,
+                None => return Err(invalid("unexpected end of file".to_string())),
+            }
+        }
+
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+
+        Ok(String::from_utf8_lossy(&self.data[start..self.pos]).into_owned())
+    }
+
+    fn number(&mut self) -> io::Result<usize> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| invalid(format!("expected a number, found '{}'", token)))
+    }
+
+    fn bytes(&mut self, count: usize) -> io::Result<&[u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + count)
+            .ok_or_else(|| invalid("unexpected end of file".to_string()))?;
+        self.pos += count;
+        Ok(bytes)
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[test]
+fn constructing_the_ppm_header() {
+    let c = Canvas::new(5, 3);
+    let ppm = c.to_ppm(&ToneMapping::default());
+    let lines: Vec<&str> = ppm.lines().take(3).collect();
+
+    assert_eq!(lines, ["P3", "5 3", "255"]);
+}
+
+#[test]
+fn constructing_the_ppm_pixel_data() {
+    let mut c = Canvas::new(5, 3);
+    c.write(0, 0, color(1.5, 0.0, 0.0));
+    c.write(2, 1, color(0.0, 0.5, 0.0));
+    c.write(4, 2, color(-0.5, 0.0, 1.0));
+    let ppm = c.to_ppm(&ToneMapping::default());
+    let lines: Vec<&str> = ppm.lines().skip(3).collect();
+
+    assert_eq!(
+        lines,
+        [
+            "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0",
+            "0 0 0 0 0 0 0 128 0 0 0 0 0 0 0",
+            "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255",
+        ]
+    );
+}
+
+#[test]
+fn splitting_long_lines_in_ppm_files() {
+    let mut c = Canvas::new(10, 2);
+    for y in 0..2 {
+        for x in 0..10 {
+            c.write(x, y, color(1.0, 0.8, 0.6));
+        }
+    }
+    let ppm = c.to_ppm(&ToneMapping::default());
+    let lines: Vec<&str> = ppm.lines().skip(3).collect();
+
+    assert_eq!(
+        lines,
+        [
+            "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+            "153 255 204 153 255 204 153 255 204 153 255 204 153",
+            "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+            "153 255 204 153 255 204 153 255 204 153 255 204 153",
+        ]
+    );
+}
+
+#[test]
+fn ppm_files_end_with_a_newline() {
+    let c = Canvas::new(5, 3);
+
+    assert!(c.to_ppm(&ToneMapping::default()).ends_with('\n'));
+}
+
+#[test]
+fn binary_ppm_has_header_and_raw_bytes() {
+    let mut c = Canvas::new(2, 1);
+    c.write(0, 0, color(1.0, 0.5, 0.0));
+    c.write(1, 0, color(0.0, 0.0, 1.0));
+    let ppm = c.to_ppm_binary(&ToneMapping::default());
+
+    assert_eq!(&ppm[..11], b"P6\n2 1\n255\n");
+    assert_eq!(&ppm[11..], &[255, 128, 0, 0, 0, 255]);
+}
+
+#[test]
+fn reading_a_plain_ppm_with_comments_and_scaling() {
+    let ppm = "P3\n# made by hand\n2 2\n100\n100 100 100  50 50 50\n# a comment\n75 50 25  0 0 0\n";
+    let c = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+
+    assert_eq!((c.width, c.height), (2, 2));
+    assert_eq!(c.pixel_at(0, 0), color(1.0, 1.0, 1.0));
+    assert_eq!(c.pixel_at(1, 0), color(0.5, 0.5, 0.5));
+    assert_eq!(c.pixel_at(0, 1), color(0.75, 0.5, 0.25));
+    assert_eq!(c.pixel_at(1, 1), color(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn ppm_round_trips_through_both_formats() {
+    let mut c = Canvas::new(3, 2);
+    c.write(0, 0, color(1.0, 0.0, 0.2));
+    c.write(2, 1, color(0.4, 0.6, 0.8));
+    let mapping = ToneMapping::default();
+
+    let plain = Canvas::from_ppm(c.to_ppm(&mapping).as_bytes()).unwrap();
+    let binary = Canvas::from_ppm(&c.to_ppm_binary(&mapping)).unwrap();
+    assert_eq!(plain, c);
+    assert_eq!(binary, c);
+}
+
+#[test]
+fn reading_a_sixteen_bit_binary_ppm() {
+    let mut ppm = b"P6 1 1 65535\n".to_vec();
+    ppm.extend([0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+    let c = Canvas::from_ppm(&ppm).unwrap();
+
+    assert_eq!(c.pixel_at(0, 0), color(1.0, 0.5, 0.0));
+}
+
+#[test]
+fn reading_a_bad_ppm_is_an_error() {
+    assert!(Canvas::from_ppm(b"P5\n1 1\n255\n\0").is_err());
+    assert!(Canvas::from_ppm(b"P6 100000000000 100000000000 255\n\0\0\0").is_err());
+    assert!(Canvas::from_ppm(b"P3 40000 40000 255\n0 0 0\n").is_err());
+    assert!(Canvas::from_ppm(b"P3\n2 1\n255\n0 0 0\n").is_err());
+    assert!(Canvas::from_ppm(b"P3\n1 1\n255\n0 x 0\n").is_err());
+    assert!(Canvas::from_ppm(b"P3\n0 0\n255\n").is_err());
+    assert!(Canvas::from_ppm(b"P6\n0 4\n255\n").is_err());
+}
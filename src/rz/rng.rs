@@ -0,0 +1,59 @@
+/// A small, deterministic pseudo-random number generator (SplitMix64).
+///
+/// Renders need to be reproducible, so anything that jitters samples takes
+/// an explicit seed rather than pulling entropy from the system.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// Seeds a generator from a list of values, e.g. the coordinates of the
+    /// point being shaded, so that the same inputs always jitter the same way.
+    pub fn from_values(seed: u64, values: &[f64]) -> Self {
+        let mut rng = Rng::new(seed);
+        for v in values {
+            rng.state ^= v.to_bits();
+            rng.next_u64();
+        }
+
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[test]
+fn same_seed_gives_same_sequence() {
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+
+    for _ in 0..10 {
+        assert_eq!(a.next_u64(), b.next_u64());
+    }
+}
+
+#[test]
+fn values_are_in_unit_interval() {
+    let mut rng = Rng::new(7);
+
+    for _ in 0..1000 {
+        let v = rng.next_f64();
+        assert!((0.0..1.0).contains(&v));
+    }
+}
@@ -0,0 +1,93 @@
+#![allow(unused_imports)]
+use super::color::*;
+use super::rng::*;
+
+/// How many rays the camera shoots through each pixel, and where.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Sampling {
+    /// One ray through the pixel center.
+    Single,
+    /// An n×n grid of rays through the centers of equal sub-pixels.
+    Grid(usize),
+    /// An n×n grid with each ray jittered at random inside its sub-pixel.
+    Jittered(usize),
+    /// A jittered 2×2 grid first, refined to a jittered n×n grid only when
+    /// those samples differ by more than `threshold` in any channel.
+    Adaptive { samples: usize, threshold: f64 },
+}
+
+impl Sampling {
+    /// The most rays this strategy will shoot through a single pixel.
+    pub fn max_samples(&self) -> usize {
+        match *self {
+            Sampling::Single => 1,
+            Sampling::Grid(n) | Sampling::Jittered(n) => n * n,
+            Sampling::Adaptive { samples, .. } => 4 + samples * samples,
+        }
+    }
+}
+
+/// Offsets within a pixel, each in `[0, 1)`, for an n×n grid of sub-pixels.
+/// With `rng` the offsets are jittered inside their cells, otherwise they sit
+/// at the cell centers.
+pub fn subpixel_offsets(n: usize, rng: Option<&mut Rng>) -> Vec<(f64, f64)> {
+    let n = n.max(1);
+    let cell = 1.0 / n as f64;
+    let mut offsets = Vec::with_capacity(n * n);
+
+    let mut rng = rng;
+    for j in 0..n {
+        for i in 0..n {
+            let (du, dv) = match rng.as_deref_mut() {
+                Some(rng) => (rng.next_f64(), rng.next_f64()),
+                None => (0.5, 0.5),
+            };
+            offsets.push(((i as f64 + du) * cell, (j as f64 + dv) * cell));
+        }
+    }
+
+    offsets
+}
+
+/// True when any channel of any color is further than `threshold` from the
+/// average of all of them.
+pub fn colors_differ(colors: &[Color], threshold: f64) -> bool {
+    let average = colors.iter().fold(Color::black(), |sum, &c| sum + c) / colors.len() as f64;
+
+    colors.iter().any(|c| {
+        (c.r - average.r).abs() > threshold
+            || (c.g - average.g).abs() > threshold
+            || (c.b - average.b).abs() > threshold
+    })
+}
+
+#[test]
+fn grid_offsets_are_cell_centers() {
+    let offsets = subpixel_offsets(2, None);
+
+    assert_eq!(
+        offsets,
+        vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+    );
+}
+
+#[test]
+fn jittered_offsets_stay_in_their_cells() {
+    let mut rng = Rng::new(3);
+    let offsets = subpixel_offsets(4, Some(&mut rng));
+
+    for (index, (u, v)) in offsets.into_iter().enumerate() {
+        let (i, j) = ((index % 4) as f64, (index / 4) as f64);
+        assert!(u >= i / 4.0 && u < (i + 1.0) / 4.0);
+        assert!(v >= j / 4.0 && v < (j + 1.0) / 4.0);
+    }
+}
+
+#[test]
+fn colors_differ_beyond_threshold() {
+    let same = [color(0.5, 0.5, 0.5), color(0.52, 0.5, 0.5)];
+    let edge = [color(0.0, 0.0, 0.0), color(1.0, 0.5, 0.5)];
+
+    assert!(!colors_differ(&same, 0.1));
+    assert!(colors_differ(&edge, 0.1));
+}
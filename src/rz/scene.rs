@@ -0,0 +1,1200 @@
+#![allow(unused_imports)]
+use super::camera::*;
+use super::canvas::*;
+use super::color::*;
+use super::cone::*;
+use super::csg::*;
+use super::cube::*;
+use super::cylinder::*;
+use super::group::*;
+use super::lights::*;
+use super::material::*;
+use super::matrix::*;
+use super::obj::*;
+use super::pattern::*;
+use super::plane::*;
+use super::shape::*;
+use super::sphere::*;
+use super::texture::*;
+use super::tuple::*;
+use super::world::*;
+use std::collections::HashMap;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+/// A world and the camera looking at it, read from a YAML scene file.
+///
+/// The format follows the ray tracer challenge: the file is a list of items,
+/// each of which either adds something to the scene or defines a reusable
+/// material or transform.
+///
+/// ```yaml
+/// - add: camera
+///   width: 100
+///   height: 50
+///   field-of-view: 1.047
+///   from: [0, 1.5, -5]
+///   to: [0, 1, 0]
+///   up: [0, 1, 0]
+///
+/// - add: light
+///   at: [-10, 10, -10]
+///   intensity: [1, 1, 1]
+///
+/// - define: shiny
+///   value: { specular: 0.9, shininess: 300 }
+///
+/// - define: red-shiny
+///   extend: shiny
+///   value: { color: [1, 0, 0] }
+///
+/// - add: sphere
+///   material: red-shiny
+///   transform:
+///     - [scale, 0.5, 0.5, 0.5]
+///     - [translate, 0, 0.5, 0]
+/// ```
+///
+/// Materials can take a `pattern` with a `type` (`stripes`, `gradient`, `rings`
+/// or `checkers`), two `colors` and an optional `transform`. A `texture`
+/// pattern instead reads an image `file` and wraps it on with a `spherical`,
+/// `planar`, `cylindrical` or `cube` `mapping`, optionally choosing a `filter`
+/// (`nearest` or `bilinear`) and how many times to `repeat` it across u and v.
+///
+/// Transforms are applied in the order they are listed. Besides `camera` and
+/// `light`, items can add `area-light`, `directional-light`, `spot-light`,
+/// `sphere`, `plane`, `cube`, `cylinder`, `cone`, `group` (with `children`),
+/// `csg` (with an `operation` of `union`, `intersection` or `difference`, and
+/// `left` and `right` shapes) and `obj` (with a `file`, relative to the scene
+/// file). A `material` on a group or csg goes to the shapes inside it that
+/// don't have one of their own.
+#[derive(Debug)]
+pub struct Scene {
+    pub world: World,
+    pub camera: Camera,
+    /// Problems that didn't stop the scene loading, such as lines skipped in
+    /// an OBJ file, each with the line of the scene file it came from.
+    pub warnings: Vec<String>,
+}
+
+/// Why a scene couldn't be loaded. Problems with the file's contents carry the
+/// line they were found on.
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, std::io::Error),
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            SceneError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for SceneError {}
+
+type SceneResult<T> = Result<T, SceneError>;
+
+impl Scene {
+    pub fn load(path: &str) -> SceneResult<Scene> {
+        let path = Path::new(path);
+        let source =
+            fs::read_to_string(path).map_err(|err| SceneError::Io(path.to_path_buf(), err))?;
+
+        Scene::parse_in(&source, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Parses a scene, resolving `obj` files against the working directory.
+    pub fn parse(source: &str) -> SceneResult<Scene> {
+        Scene::parse_in(source, Path::new(""))
+    }
+
+    fn parse_in(source: &str, dir: &Path) -> SceneResult<Scene> {
+        let root = Node::parse(source)?;
+        let mut loader = Loader {
+            dir,
+            defines: HashMap::new(),
+            camera: None,
+            lights: vec![],
+            objects: vec![],
+            warnings: vec![],
+        };
+
+        for item in root.as_seq()? {
+            loader.item(item)?;
+        }
+
+        let camera = loader.camera.ok_or(SceneError::Syntax {
+            line: root.line,
+            message: "scene has no camera".to_string(),
+        })?;
+
+        let mut world = World::new();
+        world.lights = loader.lights;
+        *world.objects_mut() = loader.objects;
+        world.build_bvh();
+
+        Ok(Scene {
+            world,
+            camera,
+            warnings: loader.warnings,
+        })
+    }
+}
+
+/// A YAML value along with the line it starts on.
+#[derive(Debug, Clone)]
+struct Node {
+    line: usize,
+    value: Value,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Scalar(String),
+    Seq(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn parse(source: &str) -> SceneResult<Node> {
+        let mut builder = NodeBuilder {
+            stack: vec![],
+            root: None,
+            error: None,
+        };
+        Parser::new(source.chars())
+            .load(&mut builder, false)
+            .map_err(|err| SceneError::Syntax {
+                line: err.marker().line(),
+                message: err.to_string(),
+            })?;
+
+        if let Some(err) = builder.error {
+            return Err(err);
+        }
+        builder.root.ok_or(SceneError::Syntax {
+            line: 1,
+            message: "scene is empty".to_string(),
+        })
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> SceneResult<T> {
+        Err(SceneError::Syntax {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    fn as_str(&self) -> SceneResult<&str> {
+        match &self.value {
+            Value::Scalar(s) => Ok(s),
+            _ => self.error("expected a single value"),
+        }
+    }
+
+    fn as_seq(&self) -> SceneResult<&[Node]> {
+        match &self.value {
+            Value::Seq(items) => Ok(items),
+            _ => self.error("expected a list"),
+        }
+    }
+
+    fn as_map(&self) -> SceneResult<&[(String, Node)]> {
+        match &self.value {
+            Value::Map(entries) => Ok(entries),
+            _ => self.error("expected a mapping"),
+        }
+    }
+
+    fn as_f64(&self) -> SceneResult<f64> {
+        let s = self.as_str()?;
+        s.parse()
+            .or_else(|_| self.error(format!("expected a number, found '{}'", s)))
+    }
+
+    fn as_usize(&self) -> SceneResult<usize> {
+        let s = self.as_str()?;
+        s.parse()
+            .or_else(|_| self.error(format!("expected a whole number, found '{}'", s)))
+    }
+
+    fn as_bool(&self) -> SceneResult<bool> {
+        match self.as_str()? {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            s => self.error(format!("expected true or false, found '{}'", s)),
+        }
+    }
+
+    fn as_triple(&self) -> SceneResult<(f64, f64, f64)> {
+        match self.as_seq()? {
+            [x, y, z] => Ok((x.as_f64()?, y.as_f64()?, z.as_f64()?)),
+            _ => self.error("expected a list of three numbers"),
+        }
+    }
+
+    fn as_point(&self) -> SceneResult<Tuple> {
+        self.as_triple().map(|(x, y, z)| point(x, y, z))
+    }
+
+    fn as_vector(&self) -> SceneResult<Tuple> {
+        self.as_triple().map(|(x, y, z)| vector(x, y, z))
+    }
+
+    fn as_color(&self) -> SceneResult<Color> {
+        self.as_triple().map(|(r, g, b)| color(r, g, b))
+    }
+
+    fn get(&self, key: &str) -> SceneResult<Option<&Node>> {
+        Ok(self
+            .as_map()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, node)| node))
+    }
+
+    fn require(&self, key: &str) -> SceneResult<&Node> {
+        match self.get(key)? {
+            Some(node) => Ok(node),
+            None => self.error(format!("missing '{}'", key)),
+        }
+    }
+
+    /// Fails on the first key not in `known`, so typos don't go unnoticed.
+    fn check_keys(&self, known: &[&str]) -> SceneResult<()> {
+        for (key, node) in self.as_map()? {
+            if !known.contains(&key.as_str()) {
+                return node.error(format!("unknown key '{}'", key));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Turns parser events into a tree of `Node`s.
+struct NodeBuilder {
+    stack: Vec<Node>,
+    root: Option<Node>,
+    error: Option<SceneError>,
+}
+
+impl NodeBuilder {
+    fn push_value(&mut self, node: Node) {
+        let Some(parent) = self.stack.last_mut() else {
+            self.root = Some(node);
+            return;
+        };
+
+        match &mut parent.value {
+            Value::Seq(items) => items.push(node),
+            // Keys and values arrive one after the other; a key is held as a
+            // pair with a placeholder until its value turns up.
+            Value::Map(entries) => match entries.last_mut() {
+                Some((_, pending @ Node { line: 0, .. })) => *pending = node,
+                _ => match node.value {
+                    Value::Scalar(key) => entries.push((
+                        key,
+                        Node {
+                            line: 0,
+                            value: Value::Scalar(String::new()),
+                        },
+                    )),
+                    _ => self.fail(node.line, "mapping keys must be plain values"),
+                },
+            },
+            Value::Scalar(_) => unreachable!("scalars have no children"),
+        }
+    }
+
+    fn fail(&mut self, line: usize, message: &str) {
+        if self.error.is_none() {
+            self.error = Some(SceneError::Syntax {
+                line,
+                message: message.to_string(),
+            });
+        }
+    }
+}
+
+impl MarkedEventReceiver for NodeBuilder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        let line = mark.line();
+        match event {
+            Event::Scalar(s, ..) => self.push_value(Node {
+                line,
+                value: Value::Scalar(s),
+            }),
+            Event::SequenceStart(_) => self.stack.push(Node {
+                line,
+                value: Value::Seq(vec![]),
+            }),
+            Event::MappingStart(_) => self.stack.push(Node {
+                line,
+                value: Value::Map(vec![]),
+            }),
+            Event::SequenceEnd | Event::MappingEnd => {
+                let node = self.stack.pop().unwrap();
+                self.push_value(node);
+            }
+            Event::Alias(_) => self.fail(line, "anchors and aliases are not supported"),
+            _ => {}
+        }
+    }
+}
+
+struct Loader<'a> {
+    dir: &'a Path,
+    defines: HashMap<String, Node>,
+    camera: Option<Camera>,
+    lights: Vec<Light>,
+    objects: Vec<Shape>,
+    warnings: Vec<String>,
+}
+
+impl Loader<'_> {
+    fn item(&mut self, item: &Node) -> SceneResult<()> {
+        if let Some(name) = item.get("define")? {
+            return self.define(item, name.as_str()?);
+        }
+
+        let kind = item.require("add")?;
+        match kind.as_str()? {
+            "camera" => self.camera = Some(camera(item)?),
+            "light" | "area-light" | "directional-light" | "spot-light" => {
+                self.lights.push(light(item)?)
+            }
+            _ => {
+                let shape = self.shape(item, None)?;
+                self.objects.push(shape);
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a named value. With `extend`, a mapping starts out as a copy of
+    /// the one it extends, and its own keys override the inherited ones.
+    fn define(&mut self, item: &Node, name: &str) -> SceneResult<()> {
+        item.check_keys(&["define", "extend", "value"])?;
+        let mut value = item.require("value")?.clone();
+
+        if let Some(base) = item.get("extend")? {
+            let inherited = self.lookup(base)?.as_map()?.to_vec();
+            let Value::Map(own) = &value.value else {
+                return value.error("only mappings can extend a definition");
+            };
+            let mut merged: Vec<(String, Node)> = inherited
+                .into_iter()
+                .filter(|(key, _)| own.iter().all(|(k, _)| k != key))
+                .collect();
+            merged.extend(own.iter().cloned());
+            value.value = Value::Map(merged);
+        }
+
+        self.defines.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &Node) -> SceneResult<&Node> {
+        let key = name.as_str()?;
+        match self.defines.get(key) {
+            Some(node) => Ok(node),
+            None => name.error(format!("'{}' has not been defined", key)),
+        }
+    }
+
+    /// Builds the shape `item` adds. Shapes without a material of their own
+    /// take `inherited`, the material of the nearest group or csg above them.
+    fn shape(&mut self, item: &Node, inherited: Option<&Material>) -> SceneResult<Shape> {
+        let kind = item.require("add")?;
+        let common = ["add", "material", "transform"];
+        let with = |extra: &[&'static str]| -> Vec<&'static str> { [&common[..], extra].concat() };
+        let material = match item.get("material")? {
+            Some(material) => Some(self.material(material)?),
+            None => inherited.cloned(),
+        };
+
+        let mut shape: Shape = match kind.as_str()? {
+            "sphere" => {
+                item.check_keys(&common)?;
+                Sphere::default().into()
+            }
+            "plane" => {
+                item.check_keys(&common)?;
+                Plane::default().into()
+            }
+            "cube" => {
+                item.check_keys(&common)?;
+                Cube::default().into()
+            }
+            "cylinder" => {
+                item.check_keys(&with(&["minimum", "maximum", "closed"]))?;
+                let (minimum, maximum, closed) = truncation(item)?;
+                Cylinder::default()
+                    .with_bounds(minimum, maximum, closed)
+                    .into()
+            }
+            "cone" => {
+                item.check_keys(&with(&["minimum", "maximum", "closed"]))?;
+                let (minimum, maximum, closed) = truncation(item)?;
+                Cone::default().with_bounds(minimum, maximum, closed).into()
+            }
+            "group" => {
+                item.check_keys(&with(&["children"]))?;
+                let mut group = Group::default();
+                for child in item.require("children")?.as_seq()? {
+                    group.add_child(self.shape(child, material.as_ref())?);
+                }
+                group.into()
+            }
+            "csg" => {
+                item.check_keys(&with(&["operation", "left", "right"]))?;
+                let operation = item.require("operation")?;
+                let operation = match operation.as_str()? {
+                    "union" => CsgOperation::Union,
+                    "intersection" => CsgOperation::Intersection,
+                    "difference" => CsgOperation::Difference,
+                    other => return operation.error(format!("unknown csg operation '{}'", other)),
+                };
+                Csg::new(
+                    operation,
+                    self.shape(item.require("left")?, material.as_ref())?,
+                    self.shape(item.require("right")?, material.as_ref())?,
+                )
+                .into()
+            }
+            "obj" => {
+                item.check_keys(&with(&["file"]))?;
+                let file = item.require("file")?;
+                let path = self.dir.join(file.as_str()?);
+                let obj = match ObjFile::load(&path.to_string_lossy()) {
+                    Ok(obj) => obj,
+                    Err(err) => return file.error(format!("{}: {}", path.display(), err)),
+                };
+                if obj.ignored > 0 {
+                    self.warnings.push(format!(
+                        "line {}: {}: skipped {} unsupported lines",
+                        file.line,
+                        path.display(),
+                        obj.ignored
+                    ));
+                }
+                obj.to_group().into()
+            }
+            other => return kind.error(format!("unknown item '{}'", other)),
+        };
+
+        if let Some(material) = material {
+            // Groups and csgs have already passed it on to their children
+            if !matches!(kind.as_str()?, "group" | "csg") {
+                shape = shape.with_material(material);
+            }
+        }
+        if let Some(transform) = item.get("transform")? {
+            let matrix = self.transform(transform)?;
+            shape = shape.with_parent(&matrix.into());
+        }
+        match &mut shape {
+            Shape::Group(group) => group.build_bvh(),
+            Shape::Csg(csg) => csg.build_bvh(),
+            _ => {}
+        }
+
+        Ok(shape)
+    }
+
+    fn material(&self, node: &Node) -> SceneResult<Material> {
+        let node = match node.value {
+            Value::Scalar(_) => self.lookup(node)?,
+            _ => node,
+        };
+
+        let mut material = Material::default_material();
+        for (key, value) in node.as_map()? {
+            match key.as_str() {
+                "color" => material.color = value.as_color()?,
+                "ambient" => material.ambient = value.as_f64()?,
+                "diffuse" => material.diffuse = value.as_f64()?,
+                "specular" => material.specular = value.as_f64()?,
+                "shininess" => material.shininess = value.as_f64()?,
+                "reflective" => material.reflective = value.as_f64()?,
+                "transparency" => material.transparency = value.as_f64()?,
+                "refractive-index" => material.refractive_index = value.as_f64()?,
+                "pattern" => material.pattern = Some(self.pattern(value)?),
+                "emissive" => material.emissive = value.as_color()?,
+                _ => return value.error(format!("unknown material property '{}'", key)),
+            }
+        }
+
+        Ok(material)
+    }
+
+    fn pattern(&self, node: &Node) -> SceneResult<Pattern> {
+        let kind = node.require("type")?;
+        let mut pattern = match kind.as_str()? {
+            "texture" => {
+                node.check_keys(&["type", "file", "mapping", "filter", "repeat", "transform"])?;
+                Pattern::texture(self.texture(node)?)
+            }
+            name => {
+                node.check_keys(&["type", "colors", "transform"])?;
+                let colors = node.require("colors")?;
+                let (a, b) = match colors.as_seq()? {
+                    [a, b] => (a.as_color()?, b.as_color()?),
+                    _ => return colors.error("expected a list of two colors"),
+                };
+
+                match name {
+                    "stripes" => Pattern::stripes(a, b),
+                    "gradient" => Pattern::gradient(a, b),
+                    "rings" => Pattern::rings(a, b),
+                    "checkers" => Pattern::checkers(a, b),
+                    other => return kind.error(format!("unknown pattern '{}'", other)),
+                }
+            }
+        };
+        if let Some(transform) = node.get("transform")? {
+            pattern = pattern.with_transform(self.transform(transform)?);
+        }
+
+        Ok(pattern)
+    }
+
+    fn texture(&self, node: &Node) -> SceneResult<Texture> {
+        let mapping = node.require("mapping")?;
+        let map = match mapping.as_str()? {
+            "spherical" => UvMap::Spherical,
+            "planar" => UvMap::Planar,
+            "cylindrical" => UvMap::Cylindrical,
+            "cube" => UvMap::Cube,
+            other => return mapping.error(format!("unknown mapping '{}'", other)),
+        };
+
+        let file = node.require("file")?;
+        let path = self.dir.join(file.as_str()?);
+        let mut texture = match Texture::load(&path.to_string_lossy(), map) {
+            Ok(texture) => texture,
+            Err(err) => return file.error(format!("{}: {}", path.display(), err)),
+        };
+
+        if let Some(filter) = node.get("filter")? {
+            texture = texture.with_filter(match filter.as_str()? {
+                "nearest" => Filter::Nearest,
+                "bilinear" => Filter::Bilinear,
+                other => return filter.error(format!("unknown filter '{}'", other)),
+            });
+        }
+        if let Some(repeat) = node.get("repeat")? {
+            texture = match repeat.as_seq()? {
+                [u, v] => texture.with_repeat(u.as_f64()?, v.as_f64()?),
+                _ => return repeat.error("expected a list of two numbers"),
+            };
+        }
+
+        Ok(texture)
+    }
+
+    /// Folds a list of operations into one matrix, applying them in order. An
+    /// entry can also name a defined list, which is applied in its place.
+    fn transform(&self, node: &Node) -> SceneResult<Matrix<4>> {
+        let mut matrix = Matrix::identity();
+
+        for op in node.as_seq()? {
+            if let Value::Scalar(_) = op.value {
+                matrix = self.transform(self.lookup(op)?)? * matrix;
+                if matrix.determinant() == 0.0 {
+                    return op.error("transform is not invertible");
+                }
+                continue;
+            }
+
+            let (name, args) = match op.as_seq()? {
+                [name, args @ ..] => (name.as_str()?, args),
+                [] => return op.error("empty transform"),
+            };
+            let args = args
+                .iter()
+                .map(Node::as_f64)
+                .collect::<SceneResult<Vec<_>>>()?;
+
+            matrix = match (name, args.as_slice()) {
+                ("translate", &[x, y, z]) => matrix.translate(x, y, z),
+                ("scale", &[x, y, z]) => matrix.scale(x, y, z),
+                ("rotate-x", &[r]) => matrix.rotate_x(r),
+                ("rotate-y", &[r]) => matrix.rotate_y(r),
+                ("rotate-z", &[r]) => matrix.rotate_z(r),
+                ("shear", &[xy, xz, yx, yz, zx, zy]) => {
+                    Matrix::shear(xy, xz, yx, yz, zx, zy) * matrix
+                }
+                ("translate" | "scale" | "rotate-x" | "rotate-y" | "rotate-z" | "shear", _) => {
+                    return op.error(format!("wrong number of arguments to '{}'", name))
+                }
+                _ => return op.error(format!("unknown transform '{}'", name)),
+            };
+            if matrix.determinant() == 0.0 {
+                return op.error("transform is not invertible");
+            }
+        }
+
+        Ok(matrix)
+    }
+}
+
+fn camera(item: &Node) -> SceneResult<Camera> {
+    item.check_keys(&[
+        "add",
+        "width",
+        "height",
+        "field-of-view",
+        "from",
+        "to",
+        "up",
+    ])?;
+
+    let size = |key| {
+        let node = item.require(key)?;
+        match node.as_usize()? {
+            0 => node.error(format!("camera '{}' must be at least 1", key)),
+            n => Ok(n),
+        }
+    };
+    let mut camera = Camera::new(
+        size("width")?,
+        size("height")?,
+        item.require("field-of-view")?.as_f64()?,
+    );
+    let view = Matrix::view(
+        item.require("from")?.as_point()?,
+        item.require("to")?.as_point()?,
+        item.require("up")?.as_vector()?,
+    );
+    // Happens when `from` and `to` coincide or `up` points along the view
+    if view.determinant().is_nan() || view.determinant() == 0.0 {
+        return item
+            .error("camera 'up' must not point along the view, and 'from' and 'to' must differ");
+    }
+    camera.set_transform(view);
+
+    Ok(camera)
+}
+
+fn light(item: &Node) -> SceneResult<Light> {
+    let intensity = item.require("intensity")?.as_color()?;
+    let direction = || {
+        let node = item.require("direction")?;
+        let direction = node.as_vector()?;
+        if direction.magnitude() == 0.0 {
+            return node.error("'direction' must not be zero");
+        }
+        Ok(direction.normalized())
+    };
+
+    let light = match item.require("add")?.as_str()? {
+        "light" => {
+            item.check_keys(&["add", "at", "intensity"])?;
+            PointLight {
+                position: item.require("at")?.as_point()?,
+                intensity,
+            }
+            .into()
+        }
+        "area-light" => {
+            item.check_keys(&[
+                "add",
+                "corner",
+                "uvec",
+                "usteps",
+                "vvec",
+                "vsteps",
+                "intensity",
+            ])?;
+            let steps = |key| {
+                let node = item.require(key)?;
+                match node.as_usize()? {
+                    0 => node.error(format!("'{}' must be at least 1", key)),
+                    n => Ok(n),
+                }
+            };
+            AreaLight::new(
+                item.require("corner")?.as_point()?,
+                item.require("uvec")?.as_vector()?,
+                steps("usteps")?,
+                item.require("vvec")?.as_vector()?,
+                steps("vsteps")?,
+                intensity,
+            )
+            .into()
+        }
+        "directional-light" => {
+            item.check_keys(&["add", "direction", "intensity"])?;
+            DirectionalLight {
+                direction: direction()?,
+                intensity,
+            }
+            .into()
+        }
+        _ => {
+            item.check_keys(&[
+                "add",
+                "at",
+                "direction",
+                "inner-angle",
+                "outer-angle",
+                "intensity",
+            ])?;
+            SpotLight {
+                position: item.require("at")?.as_point()?,
+                direction: direction()?,
+                inner_angle: item.require("inner-angle")?.as_f64()?,
+                outer_angle: item.require("outer-angle")?.as_f64()?,
+                intensity,
+            }
+            .into()
+        }
+    };
+
+    Ok(light)
+}
+
+fn truncation(item: &Node) -> SceneResult<(f64, f64, bool)> {
+    let bound = |key, default| match item.get(key)? {
+        Some(node) => node.as_f64(),
+        None => Ok(default),
+    };
+    let closed = match item.get("closed")? {
+        Some(node) => node.as_bool()?,
+        None => false,
+    };
+
+    Ok((
+        bound("minimum", f64::NEG_INFINITY)?,
+        bound("maximum", f64::INFINITY)?,
+        closed,
+    ))
+}
+
+#[cfg(test)]
+const SIMPLE_SCENE: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 0.785
+  from: [0, 1.5, -5]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+
+- define: white-material
+  value:
+    color: [1, 1, 1]
+    diffuse: 0.7
+    ambient: 0.1
+
+- define: blue-material
+  extend: white-material
+  value:
+    color: [0.537, 0.831, 0.914]
+
+- define: standard-transform
+  value:
+    - [translate, 1, -1, 1]
+    - [scale, 0.5, 0.5, 0.5]
+
+- add: sphere
+  material: blue-material
+  transform:
+    - standard-transform
+    - [scale, 3.5, 3.5, 3.5]
+";
+
+#[test]
+fn loading_a_scene() {
+    let scene = Scene::parse(SIMPLE_SCENE).unwrap();
+
+    assert_eq!(scene.world.lights.len(), 1);
+    assert_eq!(scene.world.objects().len(), 1);
+    assert_eq!(
+        scene.camera.transform().matrix(),
+        Matrix::view(
+            point(0.0, 1.5, -5.0),
+            point(0.0, 1.0, 0.0),
+            vector(0.0, 1.0, 0.0)
+        )
+    );
+}
+
+#[test]
+fn extended_material_overrides_its_base() {
+    let scene = Scene::parse(SIMPLE_SCENE).unwrap();
+    let material = scene.world.objects()[0].material();
+
+    assert_eq!(material.color, color(0.537, 0.831, 0.914));
+    assert_eq!(material.diffuse, 0.7);
+    assert_eq!(material.ambient, 0.1);
+    assert_eq!(material.specular, Material::default_material().specular);
+}
+
+#[test]
+fn transforms_apply_in_order_including_defined_ones() {
+    let scene = Scene::parse(SIMPLE_SCENE).unwrap();
+    let expected = Matrix::identity()
+        .translate(1.0, -1.0, 1.0)
+        .scale(0.5, 0.5, 0.5)
+        .scale(3.5, 3.5, 3.5);
+
+    assert_eq!(scene.world.objects()[0].transform().matrix(), expected);
+}
+
+#[test]
+fn loading_shapes_and_lights() {
+    let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: directional-light
+  direction: [0, -1, 0]
+  intensity: [1, 1, 1]
+- add: cylinder
+  minimum: 0
+  maximum: 2
+  closed: true
+- add: group
+  transform: [[translate, 0, 1, 0]]
+  children:
+    - add: cube
+    - add: plane
+";
+    let scene = Scene::parse(source).unwrap();
+
+    assert!(matches!(scene.world.lights[0], Light::Directional(_)));
+    match &scene.world.objects()[0] {
+        Shape::Cylinder(c) => assert_eq!((c.minimum, c.maximum, c.closed), (0.0, 2.0, true)),
+        other => panic!("expected a cylinder, got {:?}", other),
+    }
+    match &scene.world.objects()[1] {
+        Shape::Group(g) => assert_eq!(g.children().len(), 2),
+        other => panic!("expected a group, got {:?}", other),
+    }
+}
+
+#[test]
+fn loading_a_csg() {
+    let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: csg
+  operation: difference
+  material:
+    color: [1, 0, 0]
+  left:
+    add: cube
+  right:
+    add: sphere
+    transform: [[scale, 1.3, 1.3, 1.3]]
+";
+    let scene = Scene::parse(source).unwrap();
+
+    match &scene.world.objects()[0] {
+        Shape::Csg(csg) => {
+            assert_eq!(csg.operation, CsgOperation::Difference);
+            assert!(matches!(*csg.left, Shape::Cube(_)));
+            assert_eq!(csg.right.material().color, color(1.0, 0.0, 0.0));
+        }
+        other => panic!("expected a csg, got {:?}", other),
+    }
+
+    let err = Scene::parse(&source.replace("difference", "xor")).unwrap_err();
+    assert_eq!(err.to_string(), "line 10: unknown csg operation 'xor'");
+}
+
+#[test]
+fn errors_report_the_line() {
+    let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: sphere
+  material: no-such-material
+";
+    let err = Scene::parse(source).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "line 10: 'no-such-material' has not been defined"
+    );
+}
+
+#[test]
+fn unknown_keys_and_shapes_are_errors() {
+    let err = Scene::parse("- add: teapot\n").unwrap_err();
+    assert_eq!(err.to_string(), "line 1: unknown item 'teapot'");
+
+    let err = Scene::parse("- add: sphere\n  colour: [1, 0, 0]\n").unwrap_err();
+    assert_eq!(err.to_string(), "line 2: unknown key 'colour'");
+}
+
+#[test]
+fn degenerate_transforms_are_errors() {
+    let err = Scene::parse("- add: sphere\n  transform:\n    - [scale, 0, 1, 1]\n").unwrap_err();
+    assert_eq!(err.to_string(), "line 3: transform is not invertible");
+
+    let source = "
+- define: flatten
+  value:
+    - [scale, 1, 0, 1]
+- add: plane
+  material:
+    pattern:
+      type: stripes
+      colors: [[1, 1, 1], [0, 0, 0]]
+      transform: [flatten]
+";
+    let err = Scene::parse(source).unwrap_err();
+    assert_eq!(err.to_string(), "line 4: transform is not invertible");
+
+    let camera = SIMPLE_SCENE.replace("to: [0, 1, 0]", "to: [0, 1.5, -5]");
+    assert!(Scene::parse(&camera).is_err());
+}
+
+#[test]
+fn cameras_need_a_size() {
+    let err = Scene::parse("- add: camera\n  width: 0\n").unwrap_err();
+    assert_eq!(err.to_string(), "line 2: camera 'width' must be at least 1");
+
+    let err = Scene::parse("- add: camera\n  width: 10\n  height: 0\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 3: camera 'height' must be at least 1"
+    );
+}
+
+#[test]
+fn area_lights_need_steps() {
+    let source = "
+- add: area-light
+  corner: [-1, 2, 4]
+  uvec: [2, 0, 0]
+  usteps: 4
+  vvec: [0, 2, 0]
+  vsteps: 0
+  intensity: [1, 1, 1]
+";
+    let err = Scene::parse(source).unwrap_err();
+
+    assert_eq!(err.to_string(), "line 7: 'vsteps' must be at least 1");
+}
+
+#[test]
+fn light_directions_must_not_be_zero() {
+    let err =
+        Scene::parse("- add: directional-light\n  direction: [0, 0, 0]\n  intensity: [1, 1, 1]\n")
+            .unwrap_err();
+    assert_eq!(err.to_string(), "line 2: 'direction' must not be zero");
+
+    let source = "
+- add: spot-light
+  at: [0, 5, 0]
+  direction: [0, 0, 0]
+  inner-angle: 0.2
+  outer-angle: 0.3
+  intensity: [1, 1, 1]
+";
+    let err = Scene::parse(source).unwrap_err();
+    assert_eq!(err.to_string(), "line 4: 'direction' must not be zero");
+}
+
+#[test]
+fn yaml_syntax_errors_report_the_line() {
+    let err = Scene::parse("- add: camera\n  width: [1, 2\n").unwrap_err();
+
+    assert!(matches!(err, SceneError::Syntax { line: 3, .. }));
+}
+
+#[test]
+fn scene_without_camera_is_an_error() {
+    let err = Scene::parse("- add: light\n  at: [0, 0, 0]\n  intensity: [1, 1, 1]\n").unwrap_err();
+
+    assert_eq!(err.to_string(), "line 1: scene has no camera");
+}
+
+#[test]
+fn loading_a_material_pattern() {
+    let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors: [[1, 1, 1], [0, 0, 0]]
+      transform: [[scale, 0.5, 0.5, 0.5]]
+";
+    let scene = Scene::parse(source).unwrap();
+    let expected = Pattern::checkers(Color::white(), Color::black())
+        .with_transform(Matrix::scaling(0.5, 0.5, 0.5));
+
+    assert_eq!(scene.world.objects()[0].material().pattern, Some(expected));
+}
+
+#[test]
+fn loading_a_texture_pattern() {
+    let dir = std::env::temp_dir().join("rz-scene-texture");
+    fs::create_dir_all(&dir).unwrap();
+    let mut image = Canvas::new(2, 1);
+    image.write(0, 0, Color::white());
+    image
+        .save(&dir.join("tiles.png").to_string_lossy())
+        .unwrap();
+
+    let scene_file = dir.join("scene.yml");
+    let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: plane
+  material:
+    pattern:
+      type: texture
+      file: tiles.png
+      mapping: planar
+      filter: nearest
+      repeat: [4, 4]
+";
+    fs::write(&scene_file, source).unwrap();
+    let scene = Scene::load(&scene_file.to_string_lossy()).unwrap();
+    let material = scene.world.objects()[0].material();
+
+    match material.pattern.map(|p| p.kind) {
+        Some(PatternKind::Texture(texture)) => {
+            assert_eq!(texture.map, UvMap::Planar);
+            assert_eq!(texture.filter, Filter::Nearest);
+            assert_eq!(texture.repeat, (4.0, 4.0));
+            assert_eq!(texture.image.pixel_at(0, 0), Color::white());
+            assert_eq!(texture.image.pixel_at(1, 0), Color::black());
+        }
+        other => panic!("expected a texture, got {:?}", other),
+    }
+}
+
+#[test]
+fn empty_textures_are_errors() {
+    let dir = std::env::temp_dir().join("rz-scene-empty-texture");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("empty.ppm"), "P3\n0 0\n255\n").unwrap();
+
+    let source = "
+- add: plane
+  material:
+    pattern:
+      type: texture
+      file: empty.ppm
+      mapping: planar
+";
+    let err = Scene::parse_in(source, &dir).unwrap_err().to_string();
+
+    assert!(err.starts_with("line 6: "), "{}", err);
+}
+
+#[test]
+fn skipped_obj_lines_are_warnings() {
+    let dir = std::env::temp_dir().join("rz-scene-obj");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("triangle.obj"),
+        "mtllib shiny.mtl\nv -1 1 0\nv -1 0 0\nv 1 0 0\nusemtl shiny\nf 1 2 3\n",
+    )
+    .unwrap();
+
+    let scene_file = dir.join("scene.yml");
+    let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: obj
+  file: triangle.obj
+";
+    fs::write(&scene_file, source).unwrap();
+    let scene = Scene::load(&scene_file.to_string_lossy()).unwrap();
+
+    assert_eq!(scene.world.objects().len(), 1);
+    assert_eq!(
+        scene.warnings,
+        vec![format!(
+            "line 10: {}: skipped 2 unsupported lines",
+            dir.join("triangle.obj").display()
+        )]
+    );
+}
+
+#[test]
+fn group_materials_only_fill_in_for_children_without_one() {
+    let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: group
+  material:
+    color: [1, 0, 0]
+  children:
+    - add: sphere
+    - add: sphere
+      material:
+        color: [0, 0, 1]
+    - add: group
+      children:
+        - add: cube
+";
+    let scene = Scene::parse(source).unwrap();
+
+    let children = match &scene.world.objects()[0] {
+        Shape::Group(g) => g.children(),
+        other => panic!("expected a group, got {:?}", other),
+    };
+    assert_eq!(children[0].material().color, color(1.0, 0.0, 0.0));
+    assert_eq!(children[1].material().color, color(0.0, 0.0, 1.0));
+    match &children[2] {
+        Shape::Group(g) => assert_eq!(g.children()[0].material().color, color(1.0, 0.0, 0.0)),
+        other => panic!("expected a group, got {:?}", other),
+    }
+}
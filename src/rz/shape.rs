@@ -1,59 +1,192 @@
+use super::bounds::*;
+use super::cone::*;
+use super::csg::*;
+use super::cube::*;
+use super::cylinder::*;
+use super::group::*;
 use super::intersection::*;
 use super::material::*;
-use super::matrix::*;
 use super::plane::*;
 use super::ray::*;
+use super::smooth_triangle::*;
 use super::sphere::*;
+use super::transform::*;
+use super::triangle::*;
 use super::tuple::*;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Shape {
     Sphere(Sphere),
     Plane(Plane),
+    Cube(Cube),
+    Cylinder(Cylinder),
+    Cone(Cone),
+    Triangle(Triangle),
+    SmoothTriangle(SmoothTriangle),
+    Group(Group),
+    Csg(Csg),
 }
 
 pub trait Intersectable {
     fn material(&self) -> Material;
-    fn transform(&self) -> Matrix<4>;
+    fn transform(&self) -> &Transform;
     fn normal(&self, p: Tuple) -> Tuple;
+    fn normal_at(&self, p: Tuple, hit: &Intersection) -> Tuple;
     fn intersect(&self, ray: Ray) -> Vec<Intersection>;
+    fn bounds(&self) -> Bounds;
 }
 
 impl Intersectable for Shape {
     fn material(&self) -> Material {
-        match *self {
-            Shape::Sphere(sphere) => sphere.material,
-            Shape::Plane(plane) => plane.material,
+        match self {
+            Shape::Sphere(sphere) => sphere.material.clone(),
+            Shape::Plane(plane) => plane.material.clone(),
+            Shape::Cube(cube) => cube.material.clone(),
+            Shape::Cylinder(cylinder) => cylinder.material.clone(),
+            Shape::Cone(cone) => cone.material.clone(),
+            Shape::Triangle(triangle) => triangle.material.clone(),
+            Shape::SmoothTriangle(triangle) => triangle.material.clone(),
+            Shape::Group(_) | Shape::Csg(_) => Material::default_material(),
         }
     }
 
-    fn transform(&self) -> Matrix<4> {
-        match *self {
-            Shape::Sphere(sphere) => sphere.transform,
-            Shape::Plane(plane) => plane.transform,
+    fn transform(&self) -> &Transform {
+        match self {
+            Shape::Sphere(sphere) => &sphere.transform,
+            Shape::Plane(plane) => &plane.transform,
+            Shape::Cube(cube) => &cube.transform,
+            Shape::Cylinder(cylinder) => &cylinder.transform,
+            Shape::Cone(cone) => &cone.transform,
+            Shape::Triangle(triangle) => &triangle.transform,
+            Shape::SmoothTriangle(triangle) => &triangle.transform,
+            Shape::Group(group) => &group.transform,
+            Shape::Csg(csg) => &csg.transform,
         }
     }
 
     fn normal(&self, p: Tuple) -> Tuple {
         let local_point = self.transform().inverse() * p;
 
-        let local_normal = match *self {
+        let local_normal = match self {
             Shape::Sphere(sphere) => sphere.normal(local_point),
             Shape::Plane(plane) => plane.normal(local_point),
+            Shape::Cube(cube) => cube.normal(local_point),
+            Shape::Cylinder(cylinder) => cylinder.normal(local_point),
+            Shape::Cone(cone) => cone.normal(local_point),
+            Shape::Triangle(triangle) => triangle.normal(local_point),
+            Shape::SmoothTriangle(triangle) => triangle.normal(local_point),
+            Shape::Group(_) | Shape::Csg(_) => {
+                panic!("Groups and csgs have no surface; normals come from their children")
+            }
         };
 
-        let mut world_normal = self.transform().inverse().transposed() * local_normal;
-        world_normal.w = 0.0;
+        self.world_normal(local_normal)
+    }
 
-        world_normal.normalized()
+    fn normal_at(&self, p: Tuple, hit: &Intersection) -> Tuple {
+        match self {
+            Shape::SmoothTriangle(triangle) => {
+                let local_point = self.transform().inverse() * p;
+                self.world_normal(triangle.normal_at(local_point, hit.u, hit.v))
+            }
+            _ => self.normal(p),
+        }
     }
 
     fn intersect(&self, ray: Ray) -> Vec<Intersection> {
         let local_ray = ray.transform(self.transform().inverse());
 
-        match *self {
+        match self {
             Shape::Sphere(sphere) => sphere.intersect(local_ray),
             Shape::Plane(plane) => plane.intersect(local_ray),
+            Shape::Cube(cube) => cube.intersect(local_ray),
+            Shape::Cylinder(cylinder) => cylinder.intersect(local_ray),
+            Shape::Cone(cone) => cone.intersect(local_ray),
+            Shape::Triangle(triangle) => triangle.intersect(local_ray),
+            Shape::SmoothTriangle(triangle) => triangle.intersect(local_ray),
+            Shape::Group(group) => group
+                .intersect(local_ray)
+                .into_iter()
+                .map(|hit| Intersection {
+                    object: hit.object.with_parent(&group.transform),
+                    ..hit
+                })
+                .collect(),
+            Shape::Csg(csg) => csg
+                .intersect(local_ray)
+                .into_iter()
+                .map(|hit| Intersection {
+                    object: hit.object.with_parent(&csg.transform),
+                    ..hit
+                })
+                .collect(),
         }
     }
+
+    /// The box around this shape in its parent's space, i.e. with its transform applied.
+    fn bounds(&self) -> Bounds {
+        let local_bounds = match self {
+            Shape::Sphere(sphere) => sphere.bounds(),
+            Shape::Plane(plane) => plane.bounds(),
+            Shape::Cube(cube) => cube.bounds(),
+            Shape::Cylinder(cylinder) => cylinder.bounds(),
+            Shape::Cone(cone) => cone.bounds(),
+            Shape::Triangle(triangle) => triangle.bounds(),
+            Shape::SmoothTriangle(triangle) => triangle.bounds(),
+            Shape::Group(group) => group.bounds(),
+            Shape::Csg(csg) => csg.bounds(),
+        };
+
+        local_bounds.transformed(self.transform().matrix())
+    }
+}
+
+impl Shape {
+    /// This shape as seen from its parent's parent: its transform composed with
+    /// the transform of the group that contains it. Intersections with a group's
+    /// children are lifted this way one level at a time, so that by the time they
+    /// reach the world, normals are converted through the whole parent chain.
+    pub fn with_parent(&self, parent: &Transform) -> Shape {
+        let transform = self.transform().then(parent);
+
+        match self {
+            Shape::Sphere(sphere) => sphere.with_transform(transform).into(),
+            Shape::Plane(plane) => plane.with_transform(transform).into(),
+            Shape::Cube(cube) => cube.with_transform(transform).into(),
+            Shape::Cylinder(cylinder) => cylinder.with_transform(transform).into(),
+            Shape::Cone(cone) => cone.with_transform(transform).into(),
+            Shape::Triangle(triangle) => triangle.with_transform(transform).into(),
+            Shape::SmoothTriangle(triangle) => triangle.with_transform(transform).into(),
+            Shape::Group(group) => group.clone().with_transform(transform).into(),
+            Shape::Csg(csg) => csg.clone().with_transform(transform).into(),
+        }
+    }
+
+    /// This shape with `material`; groups and csgs hand it down to all their children.
+    pub fn with_material(&self, material: Material) -> Shape {
+        match self {
+            Shape::Sphere(sphere) => sphere.with_material(material).into(),
+            Shape::Plane(plane) => plane.with_material(material).into(),
+            Shape::Cube(cube) => cube.with_material(material).into(),
+            Shape::Cylinder(cylinder) => cylinder.with_material(material).into(),
+            Shape::Cone(cone) => cone.with_material(material).into(),
+            Shape::Triangle(triangle) => triangle.with_material(material).into(),
+            Shape::SmoothTriangle(triangle) => triangle.with_material(material).into(),
+            Shape::Group(group) => group.with_material(material).into(),
+            Shape::Csg(csg) => Csg {
+                operation: csg.operation,
+                transform: csg.transform,
+                left: Box::new(csg.left.with_material(material.clone())),
+                right: Box::new(csg.right.with_material(material)),
+            }
+            .into(),
+        }
+    }
+
+    fn world_normal(&self, local_normal: Tuple) -> Tuple {
+        let mut world_normal = self.transform().inverse_transpose() * local_normal;
+        world_normal.w = 0.0;
+
+        world_normal.normalized()
+    }
 }
@@ -0,0 +1,135 @@
+#![allow(unused_imports)]
+use super::bounds::*;
+use super::intersection::*;
+use super::material::*;
+use super::matrix::*;
+use super::ray::*;
+use super::shape::*;
+use super::transform::*;
+use super::triangle::moller_trumbore;
+use super::tuple::*;
+
+/// A triangle whose normal is interpolated from a normal at each vertex, which
+/// makes a mesh of them look smoothly curved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmoothTriangle {
+    pub transform: Transform,
+    pub material: Material,
+    pub p1: Tuple,
+    pub p2: Tuple,
+    pub p3: Tuple,
+    pub n1: Tuple,
+    pub n2: Tuple,
+    pub n3: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+    pub normal: Tuple,
+}
+
+impl From<SmoothTriangle> for Shape {
+    fn from(triangle: SmoothTriangle) -> Self {
+        Shape::SmoothTriangle(triangle)
+    }
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, n1: Tuple, n2: Tuple, n3: Tuple) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+
+        Self {
+            transform: Transform::identity(),
+            material: Material::default_material(),
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1,
+            e2,
+            normal: e2.cross(e1).normalized(),
+        }
+    }
+
+    /// The face normal, for when there's no hit to interpolate from.
+    pub fn normal(&self, _p: Tuple) -> Tuple {
+        self.normal
+    }
+
+    pub fn normal_at(&self, _p: Tuple, u: f64, v: f64) -> Tuple {
+        self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        let mut bounds = Bounds::empty();
+        bounds.add_point(self.p1);
+        bounds.add_point(self.p2);
+        bounds.add_point(self.p3);
+        bounds
+    }
+
+    pub fn with_transform(&self, transform: impl Into<Transform>) -> Self {
+        Self {
+            transform: transform.into(),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_material(&self, material: Material) -> Self {
+        Self {
+            material,
+            ..self.clone()
+        }
+    }
+
+    pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+        match moller_trumbore(self.p1, self.e1, self.e2, ray) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, self.clone().into(), u, v)],
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+fn smooth_triangle() -> SmoothTriangle {
+    SmoothTriangle::new(
+        point(0.0, 1.0, 0.0),
+        point(-1.0, 0.0, 0.0),
+        point(1.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+        vector(-1.0, 0.0, 0.0),
+        vector(1.0, 0.0, 0.0),
+    )
+}
+
+#[test]
+fn constructing_a_smooth_triangle() {
+    let t = smooth_triangle();
+
+    assert_eq!(t.p1, point(0.0, 1.0, 0.0));
+    assert_eq!(t.p2, point(-1.0, 0.0, 0.0));
+    assert_eq!(t.p3, point(1.0, 0.0, 0.0));
+    assert_eq!(t.n1, vector(0.0, 1.0, 0.0));
+    assert_eq!(t.n2, vector(-1.0, 0.0, 0.0));
+    assert_eq!(t.n3, vector(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn intersection_with_smooth_triangle_stores_uv() {
+    let obj: Shape = smooth_triangle().into();
+    let hits = obj.intersect(Ray::new(point(-0.2, 0.3, -2.0), vector(0.0, 0.0, 1.0)));
+
+    assert_eq!(hits.len(), 1);
+    assert!((hits[0].u - 0.45).abs() < 0.00001);
+    assert!((hits[0].v - 0.25).abs() < 0.00001);
+}
+
+#[test]
+fn smooth_triangle_interpolates_the_normal() {
+    let obj: Shape = smooth_triangle().into();
+    let hit = Intersection::with_uv(1.0, obj.clone(), 0.45, 0.25);
+    let n = obj.normal_at(point(0.0, 0.0, 0.0), &hit);
+
+    assert_eq!(n, vector(-0.5547, 0.83205, 0.0));
+}
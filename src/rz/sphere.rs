@@ -1,15 +1,17 @@
 #![allow(unused_imports)]
+use super::bounds::*;
 use super::intersection::*;
 use super::material::*;
 use super::matrix::*;
 use super::ray::*;
 use super::shape::*;
+use super::transform::*;
 use super::tuple::*;
 use std::f64::consts::PI;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Sphere {
-    pub transform: Matrix<4>,
+    pub transform: Transform,
     pub material: Material,
 }
 
@@ -22,26 +24,37 @@ impl From<Sphere> for Shape {
 impl Sphere {
     pub fn new(transform: Matrix<4>, material: Material) -> Self {
         Self {
-            transform,
+            transform: transform.into(),
             material,
         }
     }
 
     pub fn default() -> Self {
         Self {
-            transform: Matrix::identity(),
+            transform: Transform::identity(),
             material: Material::default_material(),
         }
     }
 
+    pub fn glass() -> Self {
+        Self {
+            transform: Transform::identity(),
+            material: Material::default_material().with_transparency(1.0, 1.5),
+        }
+    }
+
     pub fn normal(&self, p: Tuple) -> Tuple {
         p - point(0.0, 0.0, 0.0)
     }
 
-    pub fn with_transform(&self, transform: Matrix<4>) -> Self {
+    pub fn bounds(&self) -> Bounds {
+        Bounds::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0))
+    }
+
+    pub fn with_transform(&self, transform: impl Into<Transform>) -> Self {
         Self {
-            transform,
-            material: self.material,
+            transform: transform.into(),
+            material: self.material.clone(),
         }
     }
 
@@ -67,14 +80,8 @@ impl Sphere {
         }
 
         vec![
-            Intersection {
-                t: t1,
-                object: (*self).into(),
-            },
-            Intersection {
-                t: t2,
-                object: (*self).into(),
-            },
+            Intersection::new(t1, self.clone().into()),
+            Intersection::new(t2, self.clone().into()),
         ]
     }
 }
@@ -119,7 +126,7 @@ fn changing_sphere_transform() {
 
     println!("obj: {:?}", obj);
 
-    assert_eq!(obj.transform(), m);
+    assert_eq!(obj.transform().matrix(), m);
 }
 
 #[test]
@@ -0,0 +1,265 @@
+#![allow(unused_imports)]
+use super::canvas::*;
+use super::color::*;
+use super::tuple::*;
+use image::{ImageError, ImageResult};
+use std::f64::consts::PI;
+use std::io;
+use std::sync::Arc;
+
+/// How a point on an object is flattened into texture coordinates. Each
+/// mapping suits the primitive it's named after in object space: spheres,
+/// planes, cylinders and cubes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UvMap {
+    Spherical,
+    Planar,
+    Cylindrical,
+    /// Lays the six faces out as a horizontal cross in the image, four cells
+    /// wide and three high: the top row holds the up face over the front, the
+    /// middle row left, front, right and back, and the bottom row the down face.
+    Cube,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+/// An image wrapped onto an object through a `UvMap`.
+///
+/// Texture coordinates run from 0 to 1, with v = 0 at the bottom of the image.
+/// Anything outside that range wraps around, so `repeat` tiles the image that
+/// many times across each direction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Texture {
+    pub image: Arc<Canvas>,
+    pub map: UvMap,
+    pub filter: Filter,
+    pub repeat: (f64, f64),
+}
+
+impl Texture {
+    pub fn new(image: Canvas, map: UvMap) -> Self {
+        assert!(
+            image.width > 0 && image.height > 0,
+            "a texture needs at least one pixel"
+        );
+        Texture {
+            image: Arc::new(image),
+            map,
+            filter: Filter::Bilinear,
+            repeat: (1.0, 1.0),
+        }
+    }
+
+    pub fn load(file: &str, map: UvMap) -> ImageResult<Texture> {
+        let image = Canvas::load(file)?;
+        if image.width == 0 || image.height == 0 {
+            return Err(ImageError::IoError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "image has no pixels",
+            )));
+        }
+
+        Ok(Texture::new(image, map))
+    }
+
+    pub fn with_filter(self, filter: Filter) -> Self {
+        Texture { filter, ..self }
+    }
+
+    pub fn with_repeat(self, u: f64, v: f64) -> Self {
+        Texture {
+            repeat: (u, v),
+            ..self
+        }
+    }
+
+    /// The color at a point in the texture's space.
+    pub fn color_at(&self, p: Tuple) -> Color {
+        let (u, v) = self.map.uv(p);
+        self.sample(u * self.repeat.0, v * self.repeat.1)
+    }
+
+    /// The color at texture coordinates `(u, v)`.
+    pub fn sample(&self, u: f64, v: f64) -> Color {
+        let (width, height) = (self.image.width, self.image.height);
+        let x = u.rem_euclid(1.0) * width as f64;
+        let y = (1.0 - v.rem_euclid(1.0)) * height as f64;
+
+        match self.filter {
+            Filter::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            Filter::Bilinear => {
+                // Blend the four texels whose centers surround the point.
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (tx, ty) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = self.texel(x0, y0) * (1.0 - tx) + self.texel(x0 + 1, y0) * tx;
+                let bottom = self.texel(x0, y0 + 1) * (1.0 - tx) + self.texel(x0 + 1, y0 + 1) * tx;
+                top * (1.0 - ty) + bottom * ty
+            }
+        }
+    }
+
+    /// The pixel at `(x, y)`, wrapping around the edges of the image.
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = x.rem_euclid(self.image.width as i64) as usize;
+        let y = y.rem_euclid(self.image.height as i64) as usize;
+        self.image.pixel_at(x, y)
+    }
+}
+
+impl UvMap {
+    pub fn uv(&self, p: Tuple) -> (f64, f64) {
+        match self {
+            UvMap::Spherical => {
+                let radius = vector(p.x, p.y, p.z).magnitude();
+                let phi = (p.y / radius).acos();
+                (azimuth(p), 1.0 - phi / PI)
+            }
+            UvMap::Planar => (p.x.rem_euclid(1.0), p.z.rem_euclid(1.0)),
+            UvMap::Cylindrical => (azimuth(p), p.y.rem_euclid(1.0)),
+            UvMap::Cube => cube_uv(p),
+        }
+    }
+}
+
+/// How far around the y axis `p` is, from 0 to 1.
+fn azimuth(p: Tuple) -> f64 {
+    let theta = p.x.atan2(p.z);
+    1.0 - (theta / (2.0 * PI) + 0.5)
+}
+
+fn cube_uv(p: Tuple) -> (f64, f64) {
+    let face = |a: f64| (a + 1.0).rem_euclid(2.0) / 2.0;
+    let (ax, ay, az) = (p.x.abs(), p.y.abs(), p.z.abs());
+    let coord = ax.max(ay).max(az);
+
+    // The face's uv, and its column and row in the cross counting from the
+    // top left.
+    let ((u, v), (column, row)) = if coord == p.x {
+        ((face(-p.z), face(p.y)), (2.0, 1.0))
+    } else if coord == -p.x {
+        ((face(p.z), face(p.y)), (0.0, 1.0))
+    } else if coord == p.y {
+        ((face(p.x), face(-p.z)), (1.0, 0.0))
+    } else if coord == -p.y {
+        ((face(p.x), face(p.z)), (1.0, 2.0))
+    } else if coord == p.z {
+        ((face(p.x), face(p.y)), (1.0, 1.0))
+    } else {
+        ((face(-p.x), face(p.y)), (3.0, 1.0))
+    };
+
+    ((column + u) / 4.0, (2.0 - row + v) / 3.0)
+}
+
+#[cfg(test)]
+fn checker_image() -> Canvas {
+    // 2x2 pixels: white, black on top; black, white below.
+    let mut image = Canvas::new(2, 2);
+    image.write(0, 0, Color::white());
+    image.write(1, 1, Color::white());
+    image
+}
+
+#[test]
+fn spherical_mapping() {
+    let cases = [
+        (point(0.0, 0.0, -1.0), (0.0, 0.5)),
+        (point(1.0, 0.0, 0.0), (0.25, 0.5)),
+        (point(0.0, 0.0, 1.0), (0.5, 0.5)),
+        (point(-1.0, 0.0, 0.0), (0.75, 0.5)),
+        (point(0.0, 1.0, 0.0), (0.5, 1.0)),
+        (point(0.0, -1.0, 0.0), (0.5, 0.0)),
+    ];
+
+    for (p, (u, v)) in cases {
+        let (mu, mv) = UvMap::Spherical.uv(p);
+        assert!((mu - u).abs() < 1e-9 && (mv - v).abs() < 1e-9, "{:?}", p);
+    }
+}
+
+#[test]
+fn planar_mapping() {
+    assert_eq!(UvMap::Planar.uv(point(0.25, 0.0, 0.5)), (0.25, 0.5));
+    assert_eq!(UvMap::Planar.uv(point(1.25, 0.25, 0.5)), (0.25, 0.5));
+    assert_eq!(UvMap::Planar.uv(point(-0.25, 0.0, -1.75)), (0.75, 0.25));
+}
+
+#[test]
+fn cylindrical_mapping() {
+    assert_eq!(UvMap::Cylindrical.uv(point(0.0, 0.0, -1.0)), (0.0, 0.0));
+    assert_eq!(UvMap::Cylindrical.uv(point(0.0, 0.5, -1.0)), (0.0, 0.5));
+    assert_eq!(UvMap::Cylindrical.uv(point(1.0, 0.25, 0.0)), (0.25, 0.25));
+    assert_eq!(UvMap::Cylindrical.uv(point(0.0, 1.5, 1.0)), (0.5, 0.5));
+}
+
+#[test]
+fn cube_mapping_puts_each_face_in_its_cell() {
+    // The center of each face lands in the center of its cell of the cross.
+    let cases = [
+        (point(0.0, 0.0, 1.0), (1.5 / 4.0, 1.5 / 3.0)),
+        (point(1.0, 0.0, 0.0), (2.5 / 4.0, 1.5 / 3.0)),
+        (point(0.0, 0.0, -1.0), (3.5 / 4.0, 1.5 / 3.0)),
+        (point(-1.0, 0.0, 0.0), (0.5 / 4.0, 1.5 / 3.0)),
+        (point(0.0, 1.0, 0.0), (1.5 / 4.0, 2.5 / 3.0)),
+        (point(0.0, -1.0, 0.0), (1.5 / 4.0, 0.5 / 3.0)),
+    ];
+
+    for (p, (u, v)) in cases {
+        let (mu, mv) = UvMap::Cube.uv(p);
+        assert!((mu - u).abs() < 1e-9 && (mv - v).abs() < 1e-9, "{:?}", p);
+    }
+}
+
+#[test]
+fn nearest_filter_picks_the_texel() {
+    let texture = Texture::new(checker_image(), UvMap::Planar).with_filter(Filter::Nearest);
+
+    assert_eq!(texture.sample(0.25, 0.75), Color::white());
+    assert_eq!(texture.sample(0.75, 0.75), Color::black());
+    assert_eq!(texture.sample(0.25, 0.25), Color::black());
+    assert_eq!(texture.sample(0.75, 0.25), Color::white());
+}
+
+#[test]
+fn bilinear_filter_blends_neighbouring_texels() {
+    let texture = Texture::new(checker_image(), UvMap::Planar);
+
+    // Texel centers come back exactly, the corner between four texels is
+    // their average.
+    assert_eq!(texture.sample(0.25, 0.75), Color::white());
+    assert_eq!(texture.sample(0.5, 0.5), color(0.5, 0.5, 0.5));
+    assert_eq!(texture.sample(0.375, 0.75), color(0.75, 0.75, 0.75));
+}
+
+#[test]
+fn textures_tile_when_repeated() {
+    let texture = Texture::new(checker_image(), UvMap::Planar)
+        .with_filter(Filter::Nearest)
+        .with_repeat(2.0, 2.0);
+
+    // With two repeats, x = 0.25 falls in the second column of the first tile.
+    assert_eq!(texture.color_at(point(0.125, 0.0, 0.875)), Color::white());
+    assert_eq!(texture.color_at(point(0.375, 0.0, 0.875)), Color::black());
+    assert_eq!(texture.color_at(point(0.625, 0.0, 0.875)), Color::white());
+}
+
+#[test]
+#[should_panic(expected = "at least one pixel")]
+fn texture_needs_pixels() {
+    Texture::new(Canvas::new(0, 0), UvMap::Planar);
+}
+
+#[test]
+fn loading_an_empty_texture_is_an_error() {
+    let file = std::env::temp_dir().join("rz-empty-texture.ppm");
+    std::fs::write(&file, "P3\n0 0\n255\n").unwrap();
+
+    assert!(Texture::load(&file.to_string_lossy(), UvMap::Planar).is_err());
+}
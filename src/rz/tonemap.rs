@@ -0,0 +1,130 @@
+#![allow(unused_imports)]
+use super::color::*;
+
+/// How colors brighter than 1.0 are brought into the displayable range.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ToneMap {
+    /// Cuts every channel off at 1.0.
+    Clamp,
+    /// `c / (1 + c)`, which never quite reaches white.
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
+
+/// Turns linear render output into display values between 0 and 1.
+///
+/// Colors are scaled by `2^exposure`, squeezed by the tone mapping operator and,
+/// with `srgb`, encoded with the sRGB transfer curve so that they look right on
+/// an ordinary screen.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ToneMapping {
+    pub operator: ToneMap,
+    pub exposure: f64,
+    pub srgb: bool,
+}
+
+impl ToneMapping {
+    pub fn new(operator: ToneMap) -> Self {
+        ToneMapping {
+            operator,
+            exposure: 0.0,
+            srgb: false,
+        }
+    }
+
+    pub fn with_exposure(self, exposure: f64) -> Self {
+        ToneMapping { exposure, ..self }
+    }
+
+    pub fn with_srgb(self, srgb: bool) -> Self {
+        ToneMapping { srgb, ..self }
+    }
+
+    pub fn apply(&self, c: Color) -> Color {
+        let scale = 2_f64.powf(self.exposure);
+        let channel = |x: f64| {
+            let mapped = self.operator.apply(x * scale).clamp(0.0, 1.0);
+            if self.srgb {
+                srgb_encode(mapped)
+            } else {
+                mapped
+            }
+        };
+
+        Color::new(channel(c.r), channel(c.g), channel(c.b))
+    }
+}
+
+impl Default for ToneMapping {
+    /// Clamped linear output. Saving rounds each channel to the nearest 8-bit
+    /// level, where renders used to be truncated.
+    fn default() -> Self {
+        ToneMapping::new(ToneMap::Clamp)
+    }
+}
+
+impl ToneMap {
+    pub fn apply(&self, x: f64) -> f64 {
+        let x = x.max(0.0);
+        match self {
+            ToneMap::Clamp => x.min(1.0),
+            ToneMap::Reinhard => x / (1.0 + x),
+            ToneMap::Aces => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                (x * (a * x + b)) / (x * (c * x + d) + e)
+            }
+        }
+    }
+}
+
+/// The sRGB transfer curve, from linear light to encoded values.
+pub fn srgb_encode(x: f64) -> f64 {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[test]
+fn clamp_cuts_off_at_one() {
+    assert_eq!(ToneMap::Clamp.apply(0.5), 0.5);
+    assert_eq!(ToneMap::Clamp.apply(3.0), 1.0);
+    assert_eq!(ToneMap::Clamp.apply(-1.0), 0.0);
+}
+
+#[test]
+fn reinhard_compresses_highlights() {
+    assert_eq!(ToneMap::Reinhard.apply(1.0), 0.5);
+    assert_eq!(ToneMap::Reinhard.apply(3.0), 0.75);
+}
+
+#[test]
+fn aces_maps_black_to_black_and_saturates_near_white() {
+    assert_eq!(ToneMap::Aces.apply(0.0), 0.0);
+    assert!((ToneMap::Aces.apply(0.18) - 0.26690).abs() < 1e-5);
+    assert!(ToneMap::Aces.apply(100.0) > 0.99);
+}
+
+#[test]
+fn srgb_curve() {
+    assert_eq!(srgb_encode(0.0), 0.0);
+    assert!((srgb_encode(0.001) - 0.01292).abs() < 1e-9);
+    assert!((srgb_encode(0.5) - 0.73536).abs() < 1e-5);
+    assert!((srgb_encode(1.0) - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn exposure_scales_before_tone_mapping() {
+    let mapping = ToneMapping::new(ToneMap::Reinhard).with_exposure(1.0);
+
+    assert_eq!(mapping.apply(color(0.5, 1.5, 0.0)), color(0.5, 0.75, 0.0));
+}
+
+#[test]
+fn default_mapping_clamps_linear_values() {
+    let mapping = ToneMapping::default();
+
+    assert_eq!(mapping.apply(color(0.25, 1.5, -0.5)), color(0.25, 1.0, 0.0));
+}
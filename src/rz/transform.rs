@@ -0,0 +1,84 @@
+#![allow(unused_imports)]
+use super::matrix::*;
+use super::tuple::*;
+
+/// A transformation matrix along with its inverse and inverse transpose.
+///
+/// Rays and normals need the inverse every time they touch a shape, so it is
+/// computed once here instead. The fields can't be changed on their own, so the
+/// cached matrices always agree with `matrix`; to change a transform, build a new
+/// one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    matrix: Matrix<4>,
+    inverse: Matrix<4>,
+    inverse_transpose: Matrix<4>,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix<4>) -> Self {
+        let inverse = matrix.inverse();
+
+        Transform {
+            matrix,
+            inverse,
+            inverse_transpose: inverse.transposed(),
+        }
+    }
+
+    pub fn identity() -> Self {
+        Transform {
+            matrix: Matrix::identity(),
+            inverse: Matrix::identity(),
+            inverse_transpose: Matrix::identity(),
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix<4> {
+        self.matrix
+    }
+
+    pub fn inverse(&self) -> Matrix<4> {
+        self.inverse
+    }
+
+    pub fn inverse_transpose(&self) -> Matrix<4> {
+        self.inverse_transpose
+    }
+
+    /// This transform followed by `parent`, without inverting anything again.
+    pub fn then(&self, parent: &Transform) -> Self {
+        let inverse = self.inverse * parent.inverse;
+
+        Transform {
+            matrix: parent.matrix * self.matrix,
+            inverse,
+            inverse_transpose: inverse.transposed(),
+        }
+    }
+}
+
+impl From<Matrix<4>> for Transform {
+    fn from(matrix: Matrix<4>) -> Self {
+        Transform::new(matrix)
+    }
+}
+
+#[test]
+fn transform_caches_inverse_and_inverse_transpose() {
+    let m = Matrix::translation(1.0, 2.0, 3.0) * Matrix::scaling(2.0, 2.0, 2.0);
+    let t = Transform::new(m);
+
+    assert_eq!(t.matrix(), m);
+    assert_eq!(t.inverse(), m.inverse());
+    assert_eq!(t.inverse_transpose(), m.inverse().transposed());
+}
+
+#[test]
+fn composing_transforms_matches_multiplying_matrices() {
+    let child = Matrix::translation(5.0, 0.0, 0.0);
+    let parent = Matrix::rotation_y(1.0) * Matrix::scaling(1.0, 2.0, 3.0);
+    let composed = Transform::new(child).then(&Transform::new(parent));
+
+    assert_eq!(composed, Transform::new(parent * child));
+}
@@ -0,0 +1,173 @@
+#![allow(unused_imports)]
+use super::bounds::*;
+use super::intersection::*;
+use super::material::*;
+use super::matrix::*;
+use super::ray::*;
+use super::shape::*;
+use super::transform::*;
+use super::tuple::*;
+const EPSILON: f64 = 0.00001;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangle {
+    pub transform: Transform,
+    pub material: Material,
+    pub p1: Tuple,
+    pub p2: Tuple,
+    pub p3: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+    pub normal: Tuple,
+}
+
+impl From<Triangle> for Shape {
+    fn from(triangle: Triangle) -> Self {
+        Shape::Triangle(triangle)
+    }
+}
+
+impl Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+
+        Self {
+            transform: Transform::identity(),
+            material: Material::default_material(),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(e1).normalized(),
+        }
+    }
+
+    pub fn normal(&self, _p: Tuple) -> Tuple {
+        self.normal
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        let mut bounds = Bounds::empty();
+        bounds.add_point(self.p1);
+        bounds.add_point(self.p2);
+        bounds.add_point(self.p3);
+        bounds
+    }
+
+    pub fn with_transform(&self, transform: impl Into<Transform>) -> Self {
+        Self {
+            transform: transform.into(),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_material(&self, material: Material) -> Self {
+        Self {
+            material,
+            ..self.clone()
+        }
+    }
+
+    pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+        match moller_trumbore(self.p1, self.e1, self.e2, ray) {
+            Some((t, _, _)) => vec![Intersection::new(t, self.clone().into())],
+            None => vec![],
+        }
+    }
+}
+
+/// Möller–Trumbore ray/triangle intersection, returning `t` and the barycentric
+/// `u` and `v` of the hit.
+pub(crate) fn moller_trumbore(
+    p1: Tuple,
+    e1: Tuple,
+    e2: Tuple,
+    ray: Ray,
+) -> Option<(f64, f64, f64)> {
+    let dir_cross_e2 = ray.direction.cross(e2);
+    let det = e1.dot(dir_cross_e2);
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let p1_to_origin = ray.origin - p1;
+    let u = f * p1_to_origin.dot(dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(e1);
+    let v = f * ray.direction.dot(origin_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = f * e2.dot(origin_cross_e1);
+    Some((t, u, v))
+}
+
+#[test]
+fn constructing_a_triangle() {
+    let p1 = point(0.0, 1.0, 0.0);
+    let p2 = point(-1.0, 0.0, 0.0);
+    let p3 = point(1.0, 0.0, 0.0);
+    let t = Triangle::new(p1, p2, p3);
+
+    assert_eq!(t.p1, p1);
+    assert_eq!(t.p2, p2);
+    assert_eq!(t.p3, p3);
+    assert_eq!(t.e1, vector(-1.0, -1.0, 0.0));
+    assert_eq!(t.e2, vector(1.0, -1.0, 0.0));
+    assert_eq!(t.normal, vector(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn normal_of_a_triangle_is_constant() {
+    let t = Triangle::new(
+        point(0.0, 1.0, 0.0),
+        point(-1.0, 0.0, 0.0),
+        point(1.0, 0.0, 0.0),
+    );
+
+    assert_eq!(t.normal(point(0.0, 0.5, 0.0)), t.normal);
+    assert_eq!(t.normal(point(-0.5, 0.75, 0.0)), t.normal);
+    assert_eq!(t.normal(point(0.5, 0.25, 0.0)), t.normal);
+}
+
+#[test]
+fn ray_misses_a_triangle() {
+    let obj: Shape = Triangle::new(
+        point(0.0, 1.0, 0.0),
+        point(-1.0, 0.0, 0.0),
+        point(1.0, 0.0, 0.0),
+    )
+    .into();
+    let cases = [
+        (point(0.0, -1.0, -2.0), vector(0.0, 1.0, 0.0)),
+        (point(1.0, 1.0, -2.0), vector(0.0, 0.0, 1.0)),
+        (point(-1.0, 1.0, -2.0), vector(0.0, 0.0, 1.0)),
+        (point(0.0, -1.0, -2.0), vector(0.0, 0.0, 1.0)),
+    ];
+
+    for (origin, direction) in cases {
+        let hits = obj.intersect(Ray::new(origin, direction));
+        assert!(hits.is_empty());
+    }
+}
+
+#[test]
+fn ray_strikes_a_triangle() {
+    let obj: Shape = Triangle::new(
+        point(0.0, 1.0, 0.0),
+        point(-1.0, 0.0, 0.0),
+        point(1.0, 0.0, 0.0),
+    )
+    .into();
+    let hits = obj.intersect(Ray::new(point(0.0, 0.5, -2.0), vector(0.0, 0.0, 1.0)));
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].t, 2.0);
+}
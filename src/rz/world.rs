@@ -1,30 +1,48 @@
 #![allow(unused_imports)]
+use super::bvh::*;
 use super::color::*;
 use super::intersection::*;
 use super::lights::*;
 use super::material::*;
 use super::matrix::*;
+use super::plane::*;
 use super::ray::*;
 use super::shape::*;
+use super::smooth_triangle::*;
 use super::sphere::*;
+use super::transform::*;
 use super::tuple::*;
 
+use std::slice;
+
 const EPSILON: f64 = 0.00001;
 
+/// Number of times a ray may bounce off reflective surfaces before giving up.
+pub const MAX_DEPTH: usize = 5;
+
+/// The lights and objects in a scene.
+///
+/// Objects are only reachable through methods, so that changing them always
+/// throws away a bounding volume hierarchy built over the old ones.
 #[derive(Debug, PartialEq)]
 pub struct World {
-    pub light: PointLight,
-    pub objects: Vec<Shape>,
+    pub lights: Vec<Light>,
+    pub max_depth: usize,
+    objects: Vec<Shape>,
+    bvh: Option<Bvh>,
 }
 
 impl World {
     pub fn new() -> World {
         World {
-            light: PointLight {
+            lights: vec![PointLight {
                 position: point(-10.0, 10.0, -10.0),
                 intensity: Color::white(),
-            },
+            }
+            .into()],
             objects: vec![],
+            max_depth: MAX_DEPTH,
+            bvh: None,
         }
     }
 
@@ -35,6 +53,11 @@ impl World {
             diffuse: 0.7,
             specular: 0.2,
             shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            pattern: None,
+            emissive: Color::black(),
         };
         let s1: Shape = Sphere::default().with_material(material).into();
 
@@ -44,6 +67,11 @@ impl World {
             diffuse: 0.7,
             specular: 0.2,
             shininess: 0.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            pattern: None,
+            emissive: Color::black(),
         };
         let s2: Shape = Sphere::default()
             .with_transform(Matrix::scaling(0.5, 0.5, 0.5))
@@ -51,57 +79,156 @@ impl World {
             .into();
 
         World {
-            light: PointLight {
+            lights: vec![PointLight {
                 position: point(-10.0, 10.0, -10.0),
                 intensity: Color::white(),
-            },
+            }
+            .into()],
             objects: vec![s1, s2],
+            max_depth: MAX_DEPTH,
+            bvh: None,
+        }
+    }
+
+    pub fn objects(&self) -> &[Shape] {
+        &self.objects
+    }
+
+    /// The objects, for changing them. This drops the bounding volume
+    /// hierarchy, so call `build_bvh` again afterwards to get it back.
+    pub fn objects_mut(&mut self) -> &mut Vec<Shape> {
+        self.bvh = None;
+        &mut self.objects
+    }
+
+    pub fn add_object(&mut self, object: impl Into<Shape>) {
+        self.objects_mut().push(object.into());
+    }
+
+    /// Builds bounding volume hierarchies over the objects in the world and inside
+    /// any groups. This has to be called again after the objects change.
+    pub fn build_bvh(&mut self) {
+        for obj in self.objects.iter_mut() {
+            match obj {
+                Shape::Group(group) => group.build_bvh(),
+                Shape::Csg(csg) => csg.build_bvh(),
+                _ => {}
+            }
         }
+
+        self.bvh = Some(Bvh::build(&self.objects));
     }
 
     pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
-        let mut hits: Vec<Intersection> = self
-            .objects
-            .iter()
-            .flat_map(|obj| obj.intersect(ray))
-            .filter(|hit| hit.t > 0.0)
-            .collect();
+        let mut hits: Vec<Intersection> = match &self.bvh {
+            Some(bvh) => bvh.intersect(&self.objects, ray),
+            None => self
+                .objects
+                .iter()
+                .flat_map(|obj| obj.intersect(ray))
+                .collect(),
+        };
 
         hits.sort_unstable_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
         hits
     }
 
-    pub fn shade_hit(&self, comps: IntersectionInfo) -> Color {
-        let in_shadow = self.is_shadowed(&comps.over_point);
+    pub fn shade_hit(&self, comps: IntersectionInfo, remaining: usize) -> Color {
+        let material = comps.object.material();
 
-        comps.object.material().lighting(
-            self.light,
-            comps.point,
-            comps.eye,
-            comps.normal,
-            in_shadow,
-        )
+        let surface = self
+            .lights
+            .iter()
+            .map(|light| {
+                let intensity = self.intensity_at(light, &comps.over_point);
+                material.lighting(
+                    &comps.object,
+                    light,
+                    comps.point,
+                    comps.eye,
+                    comps.normal,
+                    intensity,
+                )
+            })
+            .fold(Color::black(), |acc, c| acc + c);
+        let reflected = self.reflected_color(&comps, remaining);
+        let refracted = self.refracted_color(&comps, remaining);
+
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            material.emissive + surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            material.emissive + surface + reflected + refracted
+        }
     }
 
     pub fn color(&self, ray: &Ray) -> Color {
+        self.color_at(ray, self.max_depth)
+    }
+
+    pub fn color_at(&self, ray: &Ray, remaining: usize) -> Color {
         let hits = self.intersect(*ray);
-        if hits.is_empty() {
+
+        match hit(&hits) {
+            Some(hit) => {
+                let comps = IntersectionInfo::prepare_computations(&hit, *ray, &hits);
+                self.shade_hit(comps, remaining)
+            }
+            None => Color::black(),
+        }
+    }
+
+    pub fn reflected_color(&self, comps: &IntersectionInfo, remaining: usize) -> Color {
+        let reflective = comps.object.material().reflective;
+        if remaining == 0 || reflective == 0.0 {
             return Color::black();
         }
 
-        let comps = IntersectionInfo::prepare_computations(hits[0], *ray);
-        self.shade_hit(comps)
+        let ray = Ray::new(comps.over_point, comps.reflect);
+        self.color_at(&ray, remaining - 1) * reflective
     }
 
-    pub fn is_shadowed(&self, point: &Tuple) -> bool {
-        let v = self.light.position - *point;
-        let distance = v.magnitude();
-        let direction = v.normalized();
-        let ray = Ray::new(*point, direction);
+    pub fn refracted_color(&self, comps: &IntersectionInfo, remaining: usize) -> Color {
+        let transparency = comps.object.material().transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return Color::black();
+        }
+
+        match comps.refracted_direction() {
+            Some(direction) => {
+                let ray = Ray::new(comps.under_point, direction);
+                self.color_at(&ray, remaining - 1) * transparency
+            }
+            // Total internal reflection
+            None => Color::black(),
+        }
+    }
+
+    /// The fraction of `light` that reaches `point`, from 0.0 (fully in shadow) to 1.0.
+    pub fn intensity_at(&self, light: &Light, point: &Tuple) -> f64 {
+        let samples = light.samples(*point);
+        let lit = samples
+            .iter()
+            .filter(|sample| !self.is_occluded(point, sample))
+            .count();
+
+        lit as f64 / samples.len() as f64
+    }
+
+    /// Whether no part of `light` reaches `point`.
+    pub fn is_shadowed(&self, light: &Light, point: &Tuple) -> bool {
+        light
+            .samples(*point)
+            .iter()
+            .all(|sample| self.is_occluded(point, sample))
+    }
+
+    fn is_occluded(&self, point: &Tuple, sample: &LightSample) -> bool {
+        let ray = Ray::new(*point, sample.direction);
         let intersections = self.intersect(ray);
 
-        if let Some(hit) = hit(intersections) {
-            hit.t < distance
+        if let Some(hit) = hit(&intersections) {
+            hit.t < sample.distance
         } else {
             false
         }
@@ -109,20 +236,28 @@ impl World {
 }
 
 pub struct IntersectionInfo {
-    t: f64,
-    object: Shape,
-    point: Tuple,
-    eye: Tuple,
-    normal: Tuple,
-    inside: bool,
-    over_point: Tuple,
+    pub t: f64,
+    pub object: Shape,
+    pub point: Tuple,
+    pub eye: Tuple,
+    pub normal: Tuple,
+    pub reflect: Tuple,
+    pub inside: bool,
+    pub over_point: Tuple,
+    pub under_point: Tuple,
+    pub n1: f64,
+    pub n2: f64,
 }
 
 impl IntersectionInfo {
-    pub fn prepare_computations(hit: Intersection, ray: Ray) -> IntersectionInfo {
+    pub fn prepare_computations(
+        hit: &Intersection,
+        ray: Ray,
+        hits: &[Intersection],
+    ) -> IntersectionInfo {
         let point = ray.position(hit.t);
         let eye = -ray.direction;
-        let mut normal = hit.object.normal(point);
+        let mut normal = hit.object.normal_at(point, hit);
         let inside: bool;
         if normal.dot(eye) < 0.0 {
             inside = true;
@@ -130,17 +265,82 @@ impl IntersectionInfo {
         } else {
             inside = false;
         }
+        let reflect = ray.direction.reflected(normal);
+
+        // Walk the hits in order, tracking which objects the ray is inside of, to find
+        // the refractive indices on either side of this hit.
+        let mut n1 = 1.0;
+        let mut n2 = 1.0;
+        let mut containers: Vec<&Shape> = vec![];
+        for i in hits {
+            let is_hit = i == hit;
+            if is_hit {
+                n1 = containers
+                    .last()
+                    .map_or(1.0, |obj| obj.material().refractive_index);
+            }
+
+            match containers.iter().position(|obj| **obj == i.object) {
+                Some(index) => {
+                    containers.remove(index);
+                }
+                None => containers.push(&i.object),
+            }
+
+            if is_hit {
+                n2 = containers
+                    .last()
+                    .map_or(1.0, |obj| obj.material().refractive_index);
+                break;
+            }
+        }
 
         IntersectionInfo {
             t: hit.t,
-            object: hit.object,
+            object: hit.object.clone(),
             point,
             eye,
             normal,
+            reflect,
             inside,
             over_point: point + normal * EPSILON,
+            under_point: point - normal * EPSILON,
+            n1,
+            n2,
         }
     }
+
+    /// The direction of the ray transmitted through the surface, by Snell's
+    /// law, or `None` if it is totally internally reflected.
+    pub fn refracted_direction(&self) -> Option<Tuple> {
+        let n_ratio = self.n1 / self.n2;
+        let cos_i = self.eye.dot(self.normal);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            return None;
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(self.normal * (n_ratio * cos_i - cos_t) - self.eye * n_ratio)
+    }
+
+    /// Schlick's approximation of the Fresnel reflectance at this hit.
+    pub fn schlick(&self) -> f64 {
+        let mut cos = self.eye.dot(self.normal);
+
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n * n * (1.0 - cos * cos);
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+
+            cos = (1.0 - sin2_t).sqrt();
+        }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
 }
 
 #[test]
@@ -161,7 +361,7 @@ fn precompute_intersections() {
     let ray = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
     let s = Sphere::default().into();
     let hit = Intersection::new(4.0, s);
-    let comps = IntersectionInfo::prepare_computations(hit, ray);
+    let comps = IntersectionInfo::prepare_computations(&hit, ray, slice::from_ref(&hit));
 
     assert_eq!(comps.t, hit.t);
     assert_eq!(comps.object, hit.object);
@@ -175,7 +375,7 @@ fn intersection_on_the_outside() {
     let r = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
     let shape = Sphere::default().into();
     let hit = Intersection::new(4.0, shape);
-    let comps = IntersectionInfo::prepare_computations(hit, r);
+    let comps = IntersectionInfo::prepare_computations(&hit, r, slice::from_ref(&hit));
 
     assert_eq!(comps.inside, false);
 }
@@ -185,7 +385,7 @@ fn intersection_on_the_inside() {
     let r = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0));
     let shape = Sphere::default().into();
     let hit = Intersection::new(1.0, shape);
-    let comps = IntersectionInfo::prepare_computations(hit, r);
+    let comps = IntersectionInfo::prepare_computations(&hit, r, slice::from_ref(&hit));
 
     assert_eq!(comps.point, point(0.0, 0.0, 1.0));
     assert_eq!(comps.eye, vector(0.0, 0.0, -1.0));
@@ -219,6 +419,11 @@ fn color_of_hit_behind_ray() {
         diffuse: 0.7,
         specular: 0.2,
         shininess: 0.0,
+        reflective: 0.0,
+        transparency: 0.0,
+        refractive_index: 1.0,
+        pattern: None,
+        emissive: Color::black(),
     };
     let s1: Shape = Sphere::default().with_material(material).into();
 
@@ -228,6 +433,11 @@ fn color_of_hit_behind_ray() {
         diffuse: 0.7,
         specular: 0.2,
         shininess: 0.0,
+        reflective: 0.0,
+        transparency: 0.0,
+        refractive_index: 1.0,
+        pattern: None,
+        emissive: Color::black(),
     };
     let s2: Shape = Sphere::default()
         .with_transform(Matrix::scaling(0.5, 0.5, 0.5))
@@ -235,11 +445,14 @@ fn color_of_hit_behind_ray() {
         .into();
 
     let world = World {
-        light: PointLight {
+        lights: vec![PointLight {
             position: point(-10.0, 10.0, -10.0),
             intensity: Color::white(),
-        },
+        }
+        .into()],
         objects: vec![s1, s2],
+        max_depth: MAX_DEPTH,
+        bvh: None,
     };
 
     let r = Ray::new(point(0.0, 0.0, 0.75), vector(0.0, 0.0, -1.0));
@@ -253,7 +466,7 @@ fn no_shadow_when_nothing_is_collinear_with_point_and_light() {
     let world = World::default();
     let p = point(0.0, 10.0, 0.0);
 
-    assert!(world.is_shadowed(&p) == false);
+    assert!(world.is_shadowed(&world.lights[0], &p) == false);
 }
 
 #[test]
@@ -261,7 +474,7 @@ fn shadow_when_object_between_point_and_light() {
     let world = World::default();
     let p = point(10.0, -10.0, 10.0);
 
-    assert!(world.is_shadowed(&p) == true);
+    assert!(world.is_shadowed(&world.lights[0], &p) == true);
 }
 
 #[test]
@@ -269,7 +482,7 @@ fn no_shadow_when_object_behind_light() {
     let world = World::default();
     let p = point(-20.0, 20.0, -20.0);
 
-    assert!(world.is_shadowed(&p) == false);
+    assert!(world.is_shadowed(&world.lights[0], &p) == false);
 }
 
 #[test]
@@ -277,38 +490,40 @@ fn no_shadow_when_object_behind_point() {
     let world = World::default();
     let p = point(-2.0, 2.0, -2.0);
 
-    assert!(world.is_shadowed(&p) == false);
+    assert!(world.is_shadowed(&world.lights[0], &p) == false);
 }
 
 #[test]
 fn intersection_in_shadow() {
     let mut world = World::new();
-    world.light = PointLight {
+    world.lights = vec![PointLight {
         position: point(0.0, 0.0, -10.0),
         intensity: color(1.0, 1.0, 1.0),
-    };
+    }
+    .into()];
 
     let s1: Shape = Sphere {
-        transform: Matrix::identity(),
+        transform: Transform::identity(),
         material: Material::default_material(),
     }
     .into();
 
     let s2: Shape = Sphere {
-        transform: Matrix::translation(0.0, 0.0, 10.0),
+        transform: Matrix::translation(0.0, 0.0, 10.0).into(),
         material: Material::default_material(),
     }
     .into();
 
-    world.objects = vec![s1, s2];
+    world.objects = vec![s1, s2.clone()];
 
     let ray = Ray {
         origin: point(0.0, 0.0, 5.0),
         direction: vector(0.0, 0.0, 1.0),
     };
-    let intersection = Intersection { t: 4.0, object: s2 };
-    let comps = IntersectionInfo::prepare_computations(intersection, ray);
-    let c = world.shade_hit(comps);
+    let intersection = Intersection::new(4.0, s2);
+    let comps =
+        IntersectionInfo::prepare_computations(&intersection, ray, slice::from_ref(&intersection));
+    let c = world.shade_hit(comps, MAX_DEPTH);
 
     assert_eq!(c, color(0.1, 0.1, 0.1));
 }
@@ -321,17 +536,504 @@ fn hit_should_offset_point() {
     };
 
     let shape: Shape = Sphere {
-        transform: Matrix::translation(0.0, 0.0, 1.0),
+        transform: Matrix::translation(0.0, 0.0, 1.0).into(),
         material: Material::default_material(),
     }
     .into();
 
-    let intersection = Intersection {
-        t: 5.0,
-        object: shape,
-    };
+    let intersection = Intersection::new(5.0, shape);
 
-    let comps = IntersectionInfo::prepare_computations(intersection, ray);
+    let comps =
+        IntersectionInfo::prepare_computations(&intersection, ray, slice::from_ref(&intersection));
 
     assert!(comps.over_point.z < -EPSILON / 2.0)
 }
+
+#[test]
+fn precompute_reflection_vector() {
+    let shape: Shape = Plane::default().into();
+    let sq2 = 2_f64.sqrt() / 2.0;
+    let ray = Ray::new(point(0.0, 1.0, -1.0), vector(0.0, -sq2, sq2));
+    let intersection = Intersection::new(2_f64.sqrt(), shape);
+    let comps =
+        IntersectionInfo::prepare_computations(&intersection, ray, slice::from_ref(&intersection));
+
+    assert_eq!(comps.reflect, vector(0.0, sq2, sq2));
+}
+
+#[test]
+fn reflected_color_for_nonreflective_material() {
+    let mut world = World::default();
+    let ray = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 0.0, 1.0));
+    let material = Material {
+        ambient: 1.0,
+        ..world.objects[1].material()
+    };
+    world.objects[1] = Sphere::default()
+        .with_transform(Matrix::scaling(0.5, 0.5, 0.5))
+        .with_material(material)
+        .into();
+
+    let intersection = Intersection::new(1.0, world.objects[1].clone());
+    let comps =
+        IntersectionInfo::prepare_computations(&intersection, ray, slice::from_ref(&intersection));
+
+    assert_eq!(world.reflected_color(&comps, MAX_DEPTH), Color::black());
+}
+
+#[test]
+fn reflected_color_for_reflective_material() {
+    let mut world = World::default();
+    let shape: Shape = Plane::default()
+        .with_material(Material::default_material().with_reflective(0.5))
+        .with_transform(Matrix::translation(0.0, -1.0, 0.0))
+        .into();
+    world.objects.push(shape.clone());
+
+    let sq2 = 2_f64.sqrt() / 2.0;
+    let ray = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -sq2, sq2));
+    let intersection = Intersection::new(2_f64.sqrt(), shape);
+    let comps =
+        IntersectionInfo::prepare_computations(&intersection, ray, slice::from_ref(&intersection));
+
+    assert_eq!(
+        world.reflected_color(&comps, MAX_DEPTH),
+        color(0.19032, 0.2379, 0.14274)
+    );
+}
+
+#[test]
+fn shade_hit_with_reflective_material() {
+    let mut world = World::default();
+    let shape: Shape = Plane::default()
+        .with_material(Material::default_material().with_reflective(0.5))
+        .with_transform(Matrix::translation(0.0, -1.0, 0.0))
+        .into();
+    world.objects.push(shape.clone());
+
+    let sq2 = 2_f64.sqrt() / 2.0;
+    let ray = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -sq2, sq2));
+    let intersection = Intersection::new(2_f64.sqrt(), shape);
+    let comps =
+        IntersectionInfo::prepare_computations(&intersection, ray, slice::from_ref(&intersection));
+
+    assert_eq!(
+        world.shade_hit(comps, MAX_DEPTH),
+        color(0.87677, 0.92436, 0.82918)
+    );
+}
+
+#[test]
+fn mutually_reflective_surfaces_terminate() {
+    let mut world = World::new();
+    world.lights = vec![PointLight {
+        position: point(0.0, 0.0, 0.0),
+        intensity: Color::white(),
+    }
+    .into()];
+
+    let material = Material::default_material().with_reflective(1.0);
+    let lower: Shape = Plane::default()
+        .with_material(material.clone())
+        .with_transform(Matrix::translation(0.0, -1.0, 0.0))
+        .into();
+    let upper: Shape = Plane::default()
+        .with_material(material)
+        .with_transform(Matrix::translation(0.0, 1.0, 0.0))
+        .into();
+    world.objects = vec![lower, upper];
+
+    let ray = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+    let c = world.color(&ray);
+
+    assert!(c.r > 0.0);
+}
+
+#[test]
+fn reflected_color_at_maximum_depth() {
+    let mut world = World::default();
+    let shape: Shape = Plane::default()
+        .with_material(Material::default_material().with_reflective(0.5))
+        .with_transform(Matrix::translation(0.0, -1.0, 0.0))
+        .into();
+    world.objects.push(shape.clone());
+
+    let sq2 = 2_f64.sqrt() / 2.0;
+    let ray = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -sq2, sq2));
+    let intersection = Intersection::new(2_f64.sqrt(), shape);
+    let comps =
+        IntersectionInfo::prepare_computations(&intersection, ray, slice::from_ref(&intersection));
+
+    assert_eq!(world.reflected_color(&comps, 0), Color::black());
+}
+
+#[test]
+fn finding_n1_and_n2_at_various_intersections() {
+    let a: Shape = Sphere::glass()
+        .with_transform(Matrix::scaling(2.0, 2.0, 2.0))
+        .with_material(Material::default_material().with_transparency(1.0, 1.5))
+        .into();
+    let b: Shape = Sphere::glass()
+        .with_transform(Matrix::translation(0.0, 0.0, -0.25))
+        .with_material(Material::default_material().with_transparency(1.0, 2.0))
+        .into();
+    let c: Shape = Sphere::glass()
+        .with_transform(Matrix::translation(0.0, 0.0, 0.25))
+        .with_material(Material::default_material().with_transparency(1.0, 2.5))
+        .into();
+
+    let ray = Ray::new(point(0.0, 0.0, -4.0), vector(0.0, 0.0, 1.0));
+    let hits = vec![
+        Intersection::new(2.0, a.clone()),
+        Intersection::new(2.75, b.clone()),
+        Intersection::new(3.25, c.clone()),
+        Intersection::new(4.75, b),
+        Intersection::new(5.25, c),
+        Intersection::new(6.0, a),
+    ];
+    let expected = [
+        (1.0, 1.5),
+        (1.5, 2.0),
+        (2.0, 2.5),
+        (2.5, 2.5),
+        (2.5, 1.5),
+        (1.5, 1.0),
+    ];
+
+    for (hit, (n1, n2)) in hits.iter().zip(expected) {
+        let comps = IntersectionInfo::prepare_computations(hit, ray, &hits);
+        assert_eq!(comps.n1, n1);
+        assert_eq!(comps.n2, n2);
+    }
+}
+
+#[test]
+fn under_point_is_below_the_surface() {
+    let ray = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    let shape: Shape = Sphere::glass()
+        .with_transform(Matrix::translation(0.0, 0.0, 1.0))
+        .into();
+    let intersection = Intersection::new(5.0, shape);
+    let comps =
+        IntersectionInfo::prepare_computations(&intersection, ray, slice::from_ref(&intersection));
+
+    assert!(comps.under_point.z > EPSILON / 2.0);
+    assert!(comps.point.z < comps.under_point.z);
+}
+
+#[test]
+fn refracted_color_with_opaque_surface() {
+    let world = World::default();
+    let shape = world.objects[0].clone();
+    let ray = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    let hits = vec![
+        Intersection::new(4.0, shape.clone()),
+        Intersection::new(6.0, shape),
+    ];
+    let comps = IntersectionInfo::prepare_computations(&hits[0], ray, &hits);
+
+    assert_eq!(world.refracted_color(&comps, 5), Color::black());
+}
+
+#[test]
+fn refracted_color_at_maximum_depth() {
+    let mut world = World::default();
+    let material = world.objects[0].material().with_transparency(1.0, 1.5);
+    world.objects[0] = Sphere::default().with_material(material).into();
+
+    let shape = world.objects[0].clone();
+    let ray = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    let hits = vec![
+        Intersection::new(4.0, shape.clone()),
+        Intersection::new(6.0, shape),
+    ];
+    let comps = IntersectionInfo::prepare_computations(&hits[0], ray, &hits);
+
+    assert_eq!(world.refracted_color(&comps, 0), Color::black());
+}
+
+#[test]
+fn refracted_color_under_total_internal_reflection() {
+    let mut world = World::default();
+    let material = world.objects[0].material().with_transparency(1.0, 1.5);
+    world.objects[0] = Sphere::default().with_material(material).into();
+
+    let shape = world.objects[0].clone();
+    let sq2 = 2_f64.sqrt() / 2.0;
+    let ray = Ray::new(point(0.0, 0.0, sq2), vector(0.0, 1.0, 0.0));
+    let hits = vec![
+        Intersection::new(-sq2, shape.clone()),
+        Intersection::new(sq2, shape),
+    ];
+    let comps = IntersectionInfo::prepare_computations(&hits[1], ray, &hits);
+
+    assert_eq!(world.refracted_color(&comps, 5), Color::black());
+}
+
+#[test]
+fn shade_hit_with_transparent_material() {
+    let mut world = World::default();
+    let floor: Shape = Plane::default()
+        .with_transform(Matrix::translation(0.0, -1.0, 0.0))
+        .with_material(Material::default_material().with_transparency(0.5, 1.5))
+        .into();
+    let ball: Shape = Sphere::default()
+        .with_transform(Matrix::translation(0.0, -3.5, -0.5))
+        .with_material(Material {
+            color: color(1.0, 0.0, 0.0),
+            ambient: 0.5,
+            ..Material::default_material()
+        })
+        .into();
+    world.objects.push(floor.clone());
+    world.objects.push(ball);
+
+    let sq2 = 2_f64.sqrt() / 2.0;
+    let ray = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -sq2, sq2));
+    let hits = vec![Intersection::new(2_f64.sqrt(), floor)];
+    let comps = IntersectionInfo::prepare_computations(&hits[0], ray, &hits);
+
+    assert_eq!(world.shade_hit(comps, 5), color(0.93642, 0.68642, 0.68642));
+}
+
+#[test]
+fn schlick_under_total_internal_reflection() {
+    let shape: Shape = Sphere::glass().into();
+    let sq2 = 2_f64.sqrt() / 2.0;
+    let ray = Ray::new(point(0.0, 0.0, sq2), vector(0.0, 1.0, 0.0));
+    let hits = vec![
+        Intersection::new(-sq2, shape.clone()),
+        Intersection::new(sq2, shape),
+    ];
+    let comps = IntersectionInfo::prepare_computations(&hits[1], ray, &hits);
+
+    assert_eq!(comps.schlick(), 1.0);
+}
+
+#[test]
+fn schlick_with_perpendicular_viewing_angle() {
+    let shape: Shape = Sphere::glass().into();
+    let ray = Ray::new(point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0));
+    let hits = vec![
+        Intersection::new(-1.0, shape.clone()),
+        Intersection::new(1.0, shape),
+    ];
+    let comps = IntersectionInfo::prepare_computations(&hits[1], ray, &hits);
+
+    assert!((comps.schlick() - 0.04).abs() < EPSILON);
+}
+
+#[test]
+fn schlick_with_small_angle_and_n2_greater_than_n1() {
+    let shape: Shape = Sphere::glass().into();
+    let ray = Ray::new(point(0.0, 0.99, -2.0), vector(0.0, 0.0, 1.0));
+    let hits = vec![Intersection::new(1.8589, shape)];
+    let comps = IntersectionInfo::prepare_computations(&hits[0], ray, &hits);
+
+    assert!((comps.schlick() - 0.48873).abs() < EPSILON);
+}
+
+#[test]
+fn shade_hit_with_reflective_transparent_material() {
+    let mut world = World::default();
+    let floor: Shape = Plane::default()
+        .with_transform(Matrix::translation(0.0, -1.0, 0.0))
+        .with_material(
+            Material::default_material()
+                .with_reflective(0.5)
+                .with_transparency(0.5, 1.5),
+        )
+        .into();
+    let ball: Shape = Sphere::default()
+        .with_transform(Matrix::translation(0.0, -3.5, -0.5))
+        .with_material(Material {
+            color: color(1.0, 0.0, 0.0),
+            ambient: 0.5,
+            ..Material::default_material()
+        })
+        .into();
+    world.objects.push(floor.clone());
+    world.objects.push(ball);
+
+    let sq2 = 2_f64.sqrt() / 2.0;
+    let ray = Ray::new(point(0.0, 0.0, -3.0), vector(0.0, -sq2, sq2));
+    let hits = vec![Intersection::new(2_f64.sqrt(), floor)];
+    let comps = IntersectionInfo::prepare_computations(&hits[0], ray, &hits);
+
+    assert_eq!(world.shade_hit(comps, 5), color(0.93391, 0.69643, 0.69243));
+}
+
+#[test]
+fn shade_hit_sums_contribution_of_each_light() {
+    let mut world = World::default();
+    let ray = Ray::new(point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+    let hits = world.intersect(ray);
+    let single = world.shade_hit(
+        IntersectionInfo::prepare_computations(&hits[0], ray, &hits),
+        MAX_DEPTH,
+    );
+
+    world.lights.push(world.lights[0]);
+    let double = world.shade_hit(
+        IntersectionInfo::prepare_computations(&hits[0], ray, &hits),
+        MAX_DEPTH,
+    );
+
+    assert_eq!(double, single * 2.0);
+}
+
+#[test]
+fn intensity_is_computed_per_light() {
+    let mut world = World::default();
+    world.lights.push(
+        PointLight {
+            position: point(20.0, -20.0, 20.0),
+            intensity: Color::white(),
+        }
+        .into(),
+    );
+    let p = point(10.0, -10.0, 10.0);
+
+    assert_eq!(world.intensity_at(&world.lights[0], &p), 0.0);
+    assert_eq!(world.intensity_at(&world.lights[1], &p), 1.0);
+}
+
+#[test]
+fn point_light_evaluates_intensity_at_point() {
+    let world = World::default();
+    let light = world.lights[0];
+    let cases = [
+        (point(0.0, 1.0001, 0.0), 1.0),
+        (point(-1.0001, 0.0, 0.0), 1.0),
+        (point(0.0, 0.0, -1.0001), 1.0),
+        (point(0.0, 0.0, 1.0001), 0.0),
+        (point(1.0001, 0.0, 0.0), 0.0),
+        (point(0.0, -1.0001, 0.0), 0.0),
+        (point(0.0, 0.0, 0.0), 0.0),
+    ];
+
+    for (p, expected) in cases {
+        assert_eq!(world.intensity_at(&light, &p), expected);
+    }
+}
+
+#[test]
+fn area_light_evaluates_intensity_at_point() {
+    let world = World::default();
+    let mut light = AreaLight::new(
+        point(-0.5, -0.5, -5.0),
+        vector(1.0, 0.0, 0.0),
+        2,
+        vector(0.0, 1.0, 0.0),
+        2,
+        Color::white(),
+    );
+    light.jitter = false;
+    let light: Light = light.into();
+    let cases = [
+        (point(0.0, 0.0, 2.0), 0.0),
+        (point(1.0, -1.0, 2.0), 0.25),
+        (point(1.5, 0.0, 2.0), 0.5),
+        (point(1.25, 1.25, 3.0), 0.75),
+        (point(0.0, 0.0, -2.0), 1.0),
+    ];
+
+    for (p, expected) in cases {
+        assert_eq!(world.intensity_at(&light, &p), expected);
+    }
+}
+
+#[test]
+fn intersection_lit_by_second_light_is_not_fully_shadowed() {
+    let mut world = World::new();
+    world.lights = vec![
+        PointLight {
+            position: point(0.0, 0.0, -10.0),
+            intensity: Color::white(),
+        }
+        .into(),
+        PointLight {
+            position: point(0.0, 0.0, 20.0),
+            intensity: Color::white(),
+        }
+        .into(),
+    ];
+
+    let s1: Shape = Sphere::default().into();
+    let s2: Shape = Sphere::default()
+        .with_transform(Matrix::translation(0.0, 0.0, 10.0))
+        .into();
+    world.objects = vec![s1, s2.clone()];
+
+    let ray = Ray::new(point(0.0, 0.0, 5.0), vector(0.0, 0.0, 1.0));
+    let intersection = Intersection::new(4.0, s2);
+    let comps =
+        IntersectionInfo::prepare_computations(&intersection, ray, slice::from_ref(&intersection));
+    let c = world.shade_hit(comps, MAX_DEPTH);
+
+    // Ambient from the shadowed key light, plus ambient only from the fill light
+    // since it sits behind the surface.
+    assert_eq!(c, color(0.2, 0.2, 0.2));
+}
+
+#[test]
+fn directional_light_is_shadowed_at_any_distance() {
+    let mut world = World::default();
+    world.lights = vec![DirectionalLight {
+        direction: vector(0.0, -1.0, 0.0),
+        intensity: Color::white(),
+    }
+    .into()];
+
+    assert!(world.is_shadowed(&world.lights[0], &point(0.0, -1000.0, 0.0)));
+    assert!(!world.is_shadowed(&world.lights[0], &point(0.0, 1000.0, 0.0)));
+    assert!(!world.is_shadowed(&world.lights[0], &point(5.0, -1000.0, 0.0)));
+}
+
+#[test]
+fn spot_light_is_shadowed_by_object_in_cone() {
+    let mut world = World::default();
+    world.lights = vec![SpotLight {
+        position: point(0.0, 10.0, 0.0),
+        direction: vector(0.0, -1.0, 0.0),
+        inner_angle: 0.2,
+        outer_angle: 0.4,
+        intensity: Color::white(),
+    }
+    .into()];
+
+    assert!(world.is_shadowed(&world.lights[0], &point(0.0, -5.0, 0.0)));
+    assert!(!world.is_shadowed(&world.lights[0], &point(0.0, 5.0, 0.0)));
+}
+
+#[test]
+fn prepare_computations_uses_interpolated_normal() {
+    let shape: Shape = SmoothTriangle::new(
+        point(0.0, 1.0, 0.0),
+        point(-1.0, 0.0, 0.0),
+        point(1.0, 0.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+        vector(-1.0, 0.0, 0.0),
+        vector(1.0, 0.0, 0.0),
+    )
+    .into();
+    let hit = Intersection::with_uv(1.0, shape, 0.45, 0.25);
+    let ray = Ray::new(point(-0.2, 0.3, -2.0), vector(0.0, 0.0, 1.0));
+    let comps = IntersectionInfo::prepare_computations(&hit, ray, slice::from_ref(&hit));
+
+    assert_eq!(comps.normal, vector(-0.5547, 0.83205, 0.0));
+}
+
+#[test]
+fn objects_added_after_building_the_bvh_are_still_hit() {
+    let mut world = World::default();
+    world.build_bvh();
+    world.add_object(Sphere::default().with_transform(Matrix::translation(5.0, 0.0, 0.0)));
+    let ray = Ray::new(point(5.0, 0.0, -5.0), vector(0.0, 0.0, 1.0));
+
+    assert_eq!(world.intersect(ray).len(), 2);
+
+    world.build_bvh();
+    world.objects_mut().truncate(1);
+    assert!(world.intersect(ray).is_empty());
+}